@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use bevy_ecs::prelude::*;
+use crate::ENGINE::components::SpriteComp;
+use crate::ENGINE::core::{FpsCapDeltaTime, Rect};
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+    Loop,
+    // stops on the last frame
+    Once,
+    // 0 1 2 1 0 1 2 ...
+    PingPong,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationFrame {
+    pub srs: Rect,
+    // seconds
+    pub duration: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlayMode,
+}
+
+// clips by name, shared between every entity using the same animations
+pub type AnimationSet = HashMap<String, AnimationClip>;
+
+#[derive(Component)]
+pub struct AnimationComp {
+    clips: Arc<AnimationSet>,
+    current: String,
+    frame: usize,
+    elapsed: f32,
+    reverse: bool,
+    finished: bool,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<AnimationFrame>, mode: PlayMode) -> Self {
+        Self { frames, mode }
+    }
+
+    // every frame shown for the same time
+    pub fn uniform(frames: &[Rect], duration: f32, mode: PlayMode) -> Self {
+        Self {
+            frames: frames.iter().map(|&srs| AnimationFrame { srs, duration }).collect(),
+            mode,
+        }
+    }
+}
+
+impl AnimationComp {
+    pub fn new(clips: Arc<AnimationSet>, start: &str) -> Self {
+        Self {
+            clips,
+            current: start.to_string(),
+            frame: 0,
+            elapsed: 0.0,
+            reverse: false,
+            finished: false,
+        }
+    }
+
+    // restarts only when switching to a different clip
+    pub fn play(&mut self, name: &str) {
+        if self.current == name {
+            return;
+        }
+        self.current = name.to_string();
+        self.restart();
+    }
+
+    // keeps playing the current clip by name, from the start if its frame no longer exists
+    pub fn set_clips(&mut self, clips: Arc<AnimationSet>) {
+        self.clips = clips;
+        let frame_count = self.clips.get(&self.current).map_or(0, |clip| clip.frames.len());
+        if self.frame >= frame_count {
+            self.restart();
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = 0.0;
+        self.reverse = false;
+        self.finished = false;
+    }
+
+    pub fn current_clip(&self) -> &str {
+        &self.current
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn current_frame(&self) -> Option<Rect> {
+        let clip = self.clips.get(&self.current)?;
+        return clip.frames.get(self.frame).map(|f| f.srs);
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        let clips = self.clips.clone();
+        let clip = match clips.get(&self.current) {
+            Some(clip) if !clip.frames.is_empty() => clip,
+            _ => return,
+        };
+        if self.finished {
+            return;
+        }
+
+        self.elapsed += dt;
+        // a long frame hitch can skip several frames
+        loop {
+            let duration = clip.frames[self.frame].duration;
+            if duration <= 0.0 || self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+            self.next_frame(clip);
+            if self.finished {
+                self.elapsed = 0.0;
+                return;
+            }
+        }
+    }
+
+    fn next_frame(&mut self, clip: &AnimationClip) {
+        let last = clip.frames.len() - 1;
+        match clip.mode {
+            PlayMode::Loop => {
+                self.frame = if self.frame >= last { 0 } else { self.frame + 1 };
+            },
+            PlayMode::Once => {
+                if self.frame >= last {
+                    self.finished = true;
+                } else {
+                    self.frame += 1;
+                }
+            },
+            PlayMode::PingPong => {
+                if last == 0 {
+                    return;
+                }
+                if self.reverse && self.frame == 0 {
+                    self.reverse = false;
+                } else if !self.reverse && self.frame >= last {
+                    self.reverse = true;
+                }
+                self.frame = if self.reverse { self.frame - 1 } else { self.frame + 1 };
+            },
+        }
+    }
+}
+
+
+pub fn animation_system(fps_ctrl: Res<FpsCapDeltaTime>, mut animated: Query<(&mut AnimationComp, &mut SpriteComp)>) {
+    for (mut anim, mut sprite) in animated.iter_mut() {
+        anim.advance(fps_ctrl.dt);
+        if let Some(srs) = anim.current_frame() {
+            sprite.srs = srs;
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant, SystemTime};
+use bevy_ecs::prelude::{NonSendMut, Query};
+use crate::ENGINE::animation::AnimationComp;
+use crate::ENGINE::components::{SheetSpriteComp, SpriteComp};
+use crate::ENGINE::core::{Renderer, SDLErrs, Texture};
+use crate::ENGINE::sprite_sheet::{SheetMeta, SpriteSheet};
+
+
+// cheap copyable reference to an asset owned by the AssetServer
+pub struct Handle<T> {
+    id: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+// owns every loaded texture and sheet, kept as a non-send resource next to the Renderer
+pub struct AssetServer {
+    textures: Vec<Texture>,
+    texture_sources: Vec<Source>,
+    texture_paths: HashMap<String, Handle<Texture>>,
+    sheets: Vec<SpriteSheet>,
+    sheet_sources: Vec<Source>,
+    sheet_paths: HashMap<String, Handle<SpriteSheet>>,
+    // sheets whose metadata changed in the last poll_changes call
+    reloaded_sheets: Vec<Handle<SpriteSheet>>,
+    hot_reload: bool,
+    poll_interval: Duration,
+    last_poll: Instant,
+}
+
+// file an asset was loaded from and its modification time at that point
+struct Source {
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+
+impl<T> Handle<T> {
+    pub(crate) fn new(id: usize) -> Self {
+        Self { id: id as u32, _marker: PhantomData }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl AssetServer {
+    pub fn new() -> Self {
+        Self {
+            textures: Vec::new(),
+            texture_sources: Vec::new(),
+            texture_paths: HashMap::new(),
+            sheets: Vec::new(),
+            sheet_sources: Vec::new(),
+            sheet_paths: HashMap::new(),
+            reloaded_sheets: Vec::new(),
+            hot_reload: false,
+            poll_interval: Duration::from_millis(500),
+            last_poll: Instant::now(),
+        }
+    }
+
+    // watch the files of every loaded asset and reload them when they change on disk
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        self.hot_reload = enabled;
+    }
+
+    pub fn is_hot_reload(&self) -> bool {
+        self.hot_reload
+    }
+
+    // `path` without the trailing nul, loading the same path twice returns the same handle
+    pub fn load_texture(&mut self, core: &mut Renderer, path: &str) -> Result<Handle<Texture>, SDLErrs> {
+        let key = path.trim_end_matches('\0');
+        if let Some(handle) = self.texture_paths.get(key) {
+            return Ok(*handle);
+        }
+
+        let texture = core.load_texture(&format!("{}\0", key))?;
+        let handle = Handle::new(self.textures.len());
+        self.textures.push(texture);
+        self.texture_sources.push(Source::new(key));
+        self.texture_paths.insert(key.to_string(), handle);
+        return Ok(handle);
+    }
+
+    pub fn load_sheet(&mut self, core: &mut Renderer, image: &str, meta: &str) -> Result<Handle<SpriteSheet>, SDLErrs> {
+        let key = format!("{}|{}", image.trim_end_matches('\0'), meta);
+        if let Some(handle) = self.sheet_paths.get(&key) {
+            return Ok(*handle);
+        }
+
+        let sheet = SpriteSheet {
+            texture: self.load_texture(core, image)?,
+            meta: SheetMeta::load(meta)?,
+        };
+        let handle = Handle::new(self.sheets.len());
+        self.sheets.push(sheet);
+        self.sheet_sources.push(Source::new(meta));
+        self.sheet_paths.insert(key, handle);
+        return Ok(handle);
+    }
+
+    pub fn texture(&self, handle: Handle<Texture>) -> Option<&Texture> {
+        self.textures.get(handle.id as usize)
+    }
+
+    pub fn sheet(&self, handle: Handle<SpriteSheet>) -> Option<&SpriteSheet> {
+        self.sheets.get(handle.id as usize)
+    }
+
+    // reloads every asset whose file changed since it was loaded, handles stay valid.
+    // a file that fails to load (e.g. still being written) keeps the old asset until its next change
+    pub fn poll_changes(&mut self, core: &mut Renderer) -> Vec<String> {
+        let mut reloaded = Vec::new();
+        self.reloaded_sheets.clear();
+        if !self.hot_reload || self.last_poll.elapsed() < self.poll_interval {
+            return reloaded;
+        }
+        self.last_poll = Instant::now();
+
+        for (texture, source) in self.textures.iter_mut().zip(self.texture_sources.iter_mut()) {
+            if !source.changed() {
+                continue;
+            }
+            if let Ok(new_texture) = core.load_texture(&format!("{}\0", source.path)) {
+                *texture = new_texture;
+                reloaded.push(source.path.clone());
+            }
+        }
+
+        for (i, (sheet, source)) in self.sheets.iter_mut().zip(self.sheet_sources.iter_mut()).enumerate() {
+            if !source.changed() {
+                continue;
+            }
+            if let Ok(meta) = SheetMeta::load(&source.path) {
+                sheet.meta = meta;
+                self.reloaded_sheets.push(Handle::new(i));
+                reloaded.push(source.path.clone());
+            }
+        }
+        return reloaded;
+    }
+
+    pub fn reloaded_sheets(&self) -> &[Handle<SpriteSheet>] {
+        &self.reloaded_sheets
+    }
+
+    // textures have to be destroyed before the Renderer that created them
+    pub fn clear(&mut self) {
+        self.reloaded_sheets.clear();
+        self.sheets.clear();
+        self.sheet_sources.clear();
+        self.sheet_paths.clear();
+        self.textures.clear();
+        self.texture_sources.clear();
+        self.texture_paths.clear();
+    }
+}
+
+impl Source {
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            modified: Self::modified_time(path),
+        }
+    }
+
+    fn modified_time(path: &str) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    // updates the stored time, so a change is reported once
+    fn changed(&mut self) -> bool {
+        let modified = Self::modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        return true;
+    }
+}
+
+// sprites copy their source rects at spawn, the ones tied to a reloaded sheet are refreshed here
+pub fn hot_reload_system(
+    mut assets: NonSendMut<AssetServer>,
+    mut core: NonSendMut<Renderer>,
+    mut sprites: Query<(&SheetSpriteComp, &mut SpriteComp, Option<&mut AnimationComp>)>,
+) {
+    for path in assets.poll_changes(&mut core) {
+        println!("reloaded {}", path);
+    }
+
+    for &handle in assets.reloaded_sheets() {
+        let sheet = match assets.sheet(handle) {
+            Some(sheet) => sheet,
+            None => continue,
+        };
+        for (link, mut sprite, anim) in sprites.iter_mut() {
+            if link.sheet != handle {
+                continue;
+            }
+            if let Some(srs) = sheet.frame(&link.frame) {
+                sprite.srs = srs;
+            }
+            if let Some(mut anim) = anim {
+                anim.set_clips(sheet.meta.clips());
+                if let Some(srs) = anim.current_frame() {
+                    sprite.srs = srs;
+                }
+            }
+        }
+    }
+}
+
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> std::hash::Hash for Handle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        return write!(fmt, "Handle<{}>({})", std::any::type_name::<T>(), self.id);
+    }
+}
@@ -0,0 +1,122 @@
+use bevy_ecs::prelude::*;
+use crate::ENGINE::components::{PlayerComp, SpriteComp};
+use crate::ENGINE::core::{FpsCapDeltaTime, Point2D, Rect, Vector2D};
+
+
+pub struct Camera2D {
+    // world point shown at the center of the viewport
+    pub position: Vector2D,
+    pub zoom: f32,
+    pub viewport_w: i32,
+    pub viewport_h: i32,
+    // how fast the camera catches up with its target, 1/s, 0 snaps to it
+    pub follow_speed: f32,
+    // half size of the box around the camera center the target can move in without the camera following
+    pub dead_zone: Vector2D,
+    // the camera never shows anything outside of this world rect
+    pub bounds: Option<Rect>,
+}
+
+impl Camera2D {
+    pub fn new(viewport_w: i32, viewport_h: i32) -> Self {
+        Self {
+            position: Vector2D::new(viewport_w as f32 / 2.0, viewport_h as f32 / 2.0),
+            zoom: 1.0,
+            viewport_w,
+            viewport_h,
+            follow_speed: 8.0,
+            dead_zone: Vector2D::new(48.0, 32.0),
+            bounds: None,
+        }
+    }
+
+    pub fn world_to_screen(&self, point: &Vector2D) -> Vector2D {
+        Vector2D::new(
+            (point.x - self.position.x) * self.zoom + self.viewport_w as f32 / 2.0,
+            (point.y - self.position.y) * self.zoom + self.viewport_h as f32 / 2.0,
+        )
+    }
+
+    pub fn screen_to_world(&self, point: &Vector2D) -> Vector2D {
+        Vector2D::new(
+            (point.x - self.viewport_w as f32 / 2.0) / self.zoom + self.position.x,
+            (point.y - self.viewport_h as f32 / 2.0) / self.zoom + self.position.y,
+        )
+    }
+
+    pub fn world_rect_to_screen(&self, rect: &Rect) -> Rect {
+        let top_left = self.world_to_screen(&Vector2D::new(rect.left() as f32, rect.top() as f32));
+        let bottom_right = self.world_to_screen(&Vector2D::new(rect.right() as f32, rect.bottom() as f32));
+        let x = top_left.x.floor() as i32;
+        let y = top_left.y.floor() as i32;
+        // from rounded edges so neighbouring tiles do not leave gaps when zoomed
+        return Rect::new(x, y, bottom_right.x.floor() as i32 - x, bottom_right.y.floor() as i32 - y);
+    }
+
+    // the part of the world currently on screen
+    pub fn visible_world_rect(&self) -> Rect {
+        let half_w = self.viewport_w as f32 / (2.0 * self.zoom);
+        let half_h = self.viewport_h as f32 / (2.0 * self.zoom);
+        return Rect::new(
+            (self.position.x - half_w).floor() as i32,
+            (self.position.y - half_h).floor() as i32,
+            (half_w * 2.0).ceil() as i32,
+            (half_h * 2.0).ceil() as i32,
+        );
+    }
+
+    pub fn follow(&mut self, target: &Vector2D, dt: f32) {
+        let mut desired = self.position.clone();
+        if target.x < self.position.x - self.dead_zone.x {
+            desired.x = target.x + self.dead_zone.x;
+        } else if target.x > self.position.x + self.dead_zone.x {
+            desired.x = target.x - self.dead_zone.x;
+        }
+        if target.y < self.position.y - self.dead_zone.y {
+            desired.y = target.y + self.dead_zone.y;
+        } else if target.y > self.position.y + self.dead_zone.y {
+            desired.y = target.y - self.dead_zone.y;
+        }
+
+        let t = if self.follow_speed <= 0.0 { 1.0 } else { 1.0 - (-self.follow_speed * dt).exp() };
+        self.position = self.position.lerp(&desired, t);
+        self.clamp_to_bounds();
+    }
+
+    pub fn clamp_to_bounds(&mut self) {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let half_w = self.viewport_w as f32 / (2.0 * self.zoom);
+        let half_h = self.viewport_h as f32 / (2.0 * self.zoom);
+
+        self.position.x = clamp_axis(self.position.x, half_w, bounds.left() as f32, bounds.right() as f32);
+        self.position.y = clamp_axis(self.position.y, half_h, bounds.top() as f32, bounds.bottom() as f32);
+    }
+
+    pub fn look_at(&mut self, point: Point2D) {
+        self.position = Vector2D::new(point.x() as f32, point.y() as f32);
+        self.clamp_to_bounds();
+    }
+}
+
+// bounds smaller than the view center the camera on them
+fn clamp_axis(value: f32, half_view: f32, min: f32, max: f32) -> f32 {
+    if max - min <= half_view * 2.0 {
+        return (min + max) / 2.0;
+    }
+    return value.clamp(min + half_view, max - half_view);
+}
+
+
+// follows the center of the player's interpolated sprite
+pub fn camera_follow_system(fps_ctrl: Res<FpsCapDeltaTime>, mut camera: ResMut<Camera2D>, player: Query<&SpriteComp, With<PlayerComp>>) {
+    if let Ok(sprite) = player.get_single() {
+        let target = Vector2D::new(
+            sprite.pos.x() as f32 + sprite.width() as f32 / 2.0,
+            sprite.pos.y() as f32 + sprite.height() as f32 / 2.0,
+        );
+        camera.follow(&target, fps_ctrl.dt);
+    }
+}
@@ -0,0 +1,51 @@
+use bevy_ecs::prelude::*;
+use crate::ENGINE::core::{Rect, Renderer, SDLErrs, Texture};
+
+
+// low resolution target the world is drawn into, then upscaled to the window by a whole
+// factor and centered, with black bars filling the rest. kept as a non-send resource,
+// without it the world is drawn straight to the window
+pub struct RenderCanvas {
+    pub texture: Texture,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl RenderCanvas {
+    pub fn new(core: &mut Renderer, width: i32, height: i32) -> Result<Self, SDLErrs> {
+        return Ok(Self {
+            texture: core.create_target_texture(width, height)?,
+            width,
+            height,
+        });
+    }
+
+    // largest whole scale that fits, at least 1
+    pub fn integer_scale(&self, out_w: i32, out_h: i32) -> i32 {
+        (out_w / self.width).min(out_h / self.height).max(1)
+    }
+
+    // where the canvas lands in an output of the given size
+    pub fn fit(&self, out_w: i32, out_h: i32) -> Rect {
+        let scale = self.integer_scale(out_w, out_h);
+        let w = self.width * scale;
+        let h = self.height * scale;
+        return Rect::new((out_w - w) / 2, (out_h - h) / 2, w, h);
+    }
+}
+
+
+// render_sprites_system draws into the canvas when there is one
+pub fn upscale_canvas_system(mut core: NonSendMut<Renderer>, canvas: Option<NonSend<RenderCanvas>>) -> Result<(), SDLErrs> {
+    let canvas = match canvas {
+        Some(canvas) => canvas,
+        None => return Ok(()),
+    };
+    core.set_render_target(None)?;
+    core.set_draw_color((0, 0, 0));
+    core.clear();
+
+    let (out_w, out_h) = core.output_size();
+    core.renderer_copy(&canvas.texture, None, canvas.fit(out_w, out_h))?;
+    return Ok(());
+}
@@ -0,0 +1,191 @@
+use bevy_ecs::prelude::*;
+use crate::ENGINE::components::{ColliderComp, ColliderShape, MobComp, MovementComp, PlayerComp};
+use crate::ENGINE::core::Vector2D;
+
+
+// sent once per fixed step for every overlapping pair
+pub struct ContactEvent {
+    pub a: Entity,
+    pub b: Entity,
+    // unit vector pointing from `a` towards `b`
+    pub normal: Vector2D,
+    pub depth: f32,
+}
+
+pub struct Contact {
+    pub normal: Vector2D,
+    pub depth: f32,
+}
+
+// players and mobs are disjoint, so both can be borrowed in one system
+type OnlyPlayers = (With<PlayerComp>, Without<MobComp>);
+type OnlyMobs = (With<MobComp>, Without<PlayerComp>);
+
+
+pub fn collider_center(position: &Vector2D, collider: &ColliderComp) -> Vector2D {
+    Vector2D::new(position.x + collider.offset.x, position.y + collider.offset.y)
+}
+
+pub fn contact(a_center: &Vector2D, a: &ColliderShape, b_center: &Vector2D, b: &ColliderShape) -> Option<Contact> {
+    match (a, b) {
+        (ColliderShape::Circle { radius: ra }, ColliderShape::Circle { radius: rb }) => {
+            return circle_circle(a_center, *ra, b_center, *rb);
+        },
+        (ColliderShape::Aabb { half_w: aw, half_h: ah }, ColliderShape::Aabb { half_w: bw, half_h: bh }) => {
+            return aabb_aabb(a_center, *aw, *ah, b_center, *bw, *bh);
+        },
+        (ColliderShape::Circle { radius }, ColliderShape::Aabb { half_w, half_h }) => {
+            return circle_aabb(a_center, *radius, b_center, *half_w, *half_h);
+        },
+        (ColliderShape::Aabb { half_w, half_h }, ColliderShape::Circle { radius }) => {
+            return circle_aabb(b_center, *radius, a_center, *half_w, *half_h).map(|c| Contact {
+                normal: c.normal.scale(-1.0),
+                depth: c.depth,
+            });
+        },
+    }
+}
+
+fn circle_circle(a: &Vector2D, ra: f32, b: &Vector2D, rb: f32) -> Option<Contact> {
+    let x = b.x - a.x;
+    let y = b.y - a.y;
+    let dist_squared = x * x + y * y;
+    let radii = ra + rb;
+    if dist_squared >= radii * radii {
+        return None;
+    }
+
+    let dist = dist_squared.sqrt();
+    if dist == 0.0 {
+        return Some(Contact { normal: Vector2D::new(1.0, 0.0), depth: radii });
+    }
+    return Some(Contact { normal: Vector2D::new(x / dist, y / dist), depth: radii - dist });
+}
+
+fn aabb_aabb(a: &Vector2D, aw: f32, ah: f32, b: &Vector2D, bw: f32, bh: f32) -> Option<Contact> {
+    let x = b.x - a.x;
+    let y = b.y - a.y;
+    let overlap_x = aw + bw - x.abs();
+    let overlap_y = ah + bh - y.abs();
+    if overlap_x <= 0.0 || overlap_y <= 0.0 {
+        return None;
+    }
+
+    // push out along the axis of least penetration
+    if overlap_x < overlap_y {
+        return Some(Contact { normal: Vector2D::new(if x < 0.0 { -1.0 } else { 1.0 }, 0.0), depth: overlap_x });
+    }
+    return Some(Contact { normal: Vector2D::new(0.0, if y < 0.0 { -1.0 } else { 1.0 }), depth: overlap_y });
+}
+
+// normal points from the circle towards the box
+fn circle_aabb(circle: &Vector2D, radius: f32, aabb: &Vector2D, half_w: f32, half_h: f32) -> Option<Contact> {
+    let closest_x = circle.x.clamp(aabb.x - half_w, aabb.x + half_w);
+    let closest_y = circle.y.clamp(aabb.y - half_h, aabb.y + half_h);
+    let x = closest_x - circle.x;
+    let y = closest_y - circle.y;
+    let dist_squared = x * x + y * y;
+
+    if dist_squared > 0.0 {
+        if dist_squared >= radius * radius {
+            return None;
+        }
+        let dist = dist_squared.sqrt();
+        return Some(Contact { normal: Vector2D::new(x / dist, y / dist), depth: radius - dist });
+    }
+
+    // circle center is inside the box, treat it as a box of the same radius
+    return aabb_aabb(circle, radius, radius, aabb, half_w, half_h);
+}
+
+
+// player against every mob, mobs are pushed out so a swarm can not shove the player around
+pub fn player_mob_collision_system(
+    players: Query<(Entity, &MovementComp, &ColliderComp), OnlyPlayers>,
+    mut mobs: Query<(Entity, &mut MovementComp, &ColliderComp), OnlyMobs>,
+    mut contacts: EventWriter<ContactEvent>,
+) {
+    for (pla, pla_movement, pla_collider) in players.iter() {
+        let pla_center = collider_center(&pla_movement.position, pla_collider);
+        for (mob, mut mob_movement, mob_collider) in mobs.iter_mut() {
+            let mob_center = collider_center(&mob_movement.position, mob_collider);
+            if let Some(hit) = contact(&pla_center, &pla_collider.shape, &mob_center, &mob_collider.shape) {
+                mob_movement.position.x += hit.normal.x * hit.depth;
+                mob_movement.position.y += hit.normal.y * hit.depth;
+
+                contacts.send(ContactEvent { a: pla, b: mob, normal: hit.normal, depth: hit.depth });
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(radius: f32) -> ColliderShape {
+        ColliderShape::Circle { radius }
+    }
+
+    fn aabb(half_w: f32, half_h: f32) -> ColliderShape {
+        ColliderShape::Aabb { half_w, half_h }
+    }
+
+    // (normal x, normal y, depth)
+    fn hit(a: (f32, f32), a_shape: ColliderShape, b: (f32, f32), b_shape: ColliderShape) -> Option<(f32, f32, f32)> {
+        let a = Vector2D::new(a.0, a.1);
+        let b = Vector2D::new(b.0, b.1);
+        return contact(&a, &a_shape, &b, &b_shape).map(|c| (c.normal.x, c.normal.y, c.depth));
+    }
+
+    fn assert_hit(actual: Option<(f32, f32, f32)>, expected: (f32, f32, f32)) {
+        let (x, y, depth) = actual.expect("shapes should touch");
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        assert!(close(x, expected.0) && close(y, expected.1) && close(depth, expected.2), "{:?} != {:?}", (x, y, depth), expected);
+    }
+
+    #[test]
+    fn circles() {
+        assert_hit(hit((0.0, 0.0), circle(10.0), (15.0, 0.0), circle(10.0)), (1.0, 0.0, 5.0));
+        assert_hit(hit((0.0, 0.0), circle(10.0), (0.0, -12.0), circle(5.0)), (0.0, -1.0, 3.0));
+        // touching edges do not count
+        assert!(hit((0.0, 0.0), circle(10.0), (20.0, 0.0), circle(10.0)).is_none());
+        // same center, pushed apart along x by the full radii
+        assert_hit(hit((3.0, 3.0), circle(10.0), (3.0, 3.0), circle(10.0)), (1.0, 0.0, 20.0));
+    }
+
+    #[test]
+    fn boxes_separate_along_the_shallower_axis() {
+        assert_hit(hit((0.0, 0.0), aabb(10.0, 10.0), (-15.0, 5.0), aabb(10.0, 10.0)), (-1.0, 0.0, 5.0));
+        assert_hit(hit((0.0, 0.0), aabb(10.0, 10.0), (2.0, -18.0), aabb(10.0, 10.0)), (0.0, -1.0, 2.0));
+        assert!(hit((0.0, 0.0), aabb(10.0, 10.0), (25.0, 0.0), aabb(10.0, 10.0)).is_none());
+        // same center, equal overlaps resolve along y
+        assert_hit(hit((0.0, 0.0), aabb(4.0, 4.0), (0.0, 0.0), aabb(4.0, 4.0)), (0.0, 1.0, 8.0));
+    }
+
+    #[test]
+    fn circle_against_box() {
+        assert_hit(hit((0.0, 0.0), circle(10.0), (12.0, 0.0), aabb(5.0, 5.0)), (1.0, 0.0, 3.0));
+        // closest point is the corner, sqrt(32) away
+        assert!(hit((0.0, 0.0), circle(5.0), (9.0, 9.0), aabb(5.0, 5.0)).is_none());
+        let corner = hit((0.0, 0.0), circle(6.0), (9.0, 9.0), aabb(5.0, 5.0));
+        let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+        assert_hit(corner, (half_sqrt2, half_sqrt2, 6.0 - 32.0f32.sqrt()));
+    }
+
+    #[test]
+    fn box_against_circle_points_from_the_box() {
+        assert_hit(hit((12.0, 0.0), aabb(5.0, 5.0), (0.0, 0.0), circle(10.0)), (-1.0, 0.0, 3.0));
+        assert_hit(hit((0.0, 20.0), aabb(5.0, 5.0), (0.0, 8.0), circle(10.0)), (0.0, -1.0, 3.0));
+    }
+
+    #[test]
+    fn circle_center_inside_the_box_falls_back_to_boxes() {
+        // treated as a 4x4 half size box at (1, 0)
+        assert_hit(hit((1.0, 0.0), circle(4.0), (0.0, 0.0), aabb(5.0, 5.0)), (-1.0, 0.0, 8.0));
+        assert_hit(hit((0.0, 0.0), aabb(5.0, 5.0), (1.0, 0.0), circle(4.0)), (1.0, 0.0, 8.0));
+        // zero distance
+        assert_hit(hit((0.0, 0.0), circle(3.0), (0.0, 0.0), aabb(5.0, 5.0)), (0.0, 1.0, 8.0));
+    }
+}
@@ -1,11 +1,65 @@
 use bevy_ecs::prelude::Component;
-use crate::ENGINE::core::{Point2D, Rect, Vector2D};
+use crate::ENGINE::assets::Handle;
+use bevy_ecs::prelude::Entity;
+use crate::ENGINE::core::{BlendMode, Color, Point2D, Rect, RendererFlip, Texture, Vector2D};
+use crate::ENGINE::sprite_sheet::SpriteSheet;
 
 
 #[derive( Component)]
 pub struct SpriteComp{
+    pub texture: Handle<Texture>,
     pub srs: Rect,
     pub pos: Point2D,
+    // whole-pixel upscale of the source rect on screen
+    pub scale: i32,
+    // multiplies the texture's colors, white draws it unchanged. the alpha channel is ignored
+    pub tint: Color,
+    pub alpha: u8,
+    pub blend: BlendMode,
+    pub flip: RendererFlip,
+    // degrees, clockwise
+    pub rotation: f64,
+    // point rotated around, in pixels of the scaled sprite (before camera zoom) from its top left. None is the center
+    pub pivot: Option<Point2D>,
+}
+
+impl SpriteComp {
+    pub fn new(texture: Handle<Texture>, srs: Rect, pos: Point2D) -> Self {
+        Self {
+            texture,
+            srs,
+            pos,
+            scale: 1,
+            tint: Color::WHITE,
+            alpha: 0xff,
+            blend: BlendMode::Blend,
+            flip: RendererFlip::None,
+            rotation: 0.0,
+            pivot: None,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.srs.width() * self.scale
+    }
+
+    pub fn height(&self) -> i32 {
+        self.srs.height() * self.scale
+    }
+
+    pub fn is_transformed(&self) -> bool {
+        self.rotation != 0.0 || self.flip != RendererFlip::None
+    }
+}
+
+
+// sprite whose source rect comes from a sheet, looked up again when the sheet is hot reloaded.
+// an AnimationComp on the same entity gets the sheet's new clips
+#[derive(Component)]
+pub struct SheetSpriteComp {
+    pub sheet: Handle<SpriteSheet>,
+    // shown when there is no animation frame
+    pub frame: String,
 }
 
 
@@ -15,6 +69,12 @@ pub struct PlayerComp{
 
 
 
+// sprite that follows its owner and points at the nearest mob
+#[derive(Component)]
+pub struct WeaponComp {
+    pub owner: Entity,
+}
+
 #[derive( Component)]
 pub struct MobComp{
     pub rotate_dir: Vector2D,
@@ -22,12 +82,91 @@ pub struct MobComp{
 
 
 
+// px/s^2, cleared after every integration step
 #[derive(Component, Debug)]
 pub struct AccelerationComp {
     pub acceleration: Vector2D,
+    // what the last step applied, for the debug overlay
+    pub last_acceleration: Vector2D,
+}
+
+// px/s
+#[derive(Component, Debug)]
+pub struct VelocityComp {
+    pub velocity: Vector2D,
+}
+
+#[derive(Component, Debug)]
+pub struct SpeedComp {
+    // acceleration can not push the velocity past this, knockback can and is slowed by friction
+    pub max_speed: f32,
+    // acceleration applied when steering at full strength
+    pub thrust: f32,
+    // fraction of velocity lost per second
+    pub friction: f32,
 }
 
 #[derive(Component)]
 pub struct MovementComp {
     pub position: Vector2D,
+    // position at the start of the last fixed step, used to interpolate rendering
+    pub prev_position: Vector2D,
+}
+
+// fades the sprite out and despawns the entity once it is invisible
+#[derive(Component, Debug)]
+pub struct FadeOutComp {
+    pub duration: f32,
+    pub remaining: f32,
+}
+
+impl FadeOutComp {
+    pub fn new(duration: f32) -> Self {
+        Self { duration, remaining: duration }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ColliderShape {
+    Circle { radius: f32 },
+    Aabb { half_w: f32, half_h: f32 },
+}
+
+#[derive(Component, Debug)]
+pub struct ColliderComp {
+    pub shape: ColliderShape,
+    // from MovementComp.position (the sprite's top left) to the shape's center
+    pub offset: Vector2D,
+}
+
+#[derive(Component, Debug)]
+pub struct HealthComp {
+    pub hp: i32,
+    pub max_hp: i32,
+    // seconds of invulnerability granted after every hit
+    pub iframes: f32,
+    // seconds left before the entity can be hurt again
+    pub invulnerable: f32,
+}
+
+impl HealthComp {
+    pub fn new(max_hp: i32, iframes: f32) -> Self {
+        Self {
+            hp: max_hp,
+            max_hp,
+            iframes,
+            invulnerable: 0.0,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.hp <= 0
+    }
+}
+
+// damage dealt to whatever this entity touches
+#[derive(Component, Debug)]
+pub struct ContactDamageComp {
+    pub damage: i32,
+    pub knockback: f32,
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::time;
 use sdl2_sys as c;
-use crate::{print_error, WINDOW_HEIGHT, WINDOW_WIDHT};
+use crate::print_error;
 
 
 // ================ enums
@@ -13,6 +13,7 @@ pub enum SDLErrs {
     LoadTextureErr,
     TTFQueryTextureErr,
     TTFFontErr,
+    GlyphTooLargeErr,
     DisplayModeErr,
     WindowFullScreenErr,
     LoadSurfaceErr,
@@ -20,10 +21,20 @@ pub enum SDLErrs {
     BlitSurfaceErr,
     CreateTextureFromSurfaceErr,
     FillRectFaild,
+    DrawRectErr,
+    DrawLineErr,
+    DrawPointErr,
+    RenderGeometryErr,
     RenderErr,
+    LoadSheetMetaErr,
+    CreateTextureErr,
+    UpdateTextureErr,
+    RenderTargetErr,
+    ReadPixelsErr,
+    SaveImageErr,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum RendererFlip {
     None = 0,
     Horizontal = 1,
@@ -31,6 +42,38 @@ pub enum RendererFlip {
 }
 
 
+// how a texture or draw call is combined with what is already on screen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlendMode {
+    // overwrite, alpha ignored
+    None,
+    // regular alpha blending
+    Blend,
+    // adds to the destination, for glows and light
+    Add,
+    // multiplies the destination, for shadows
+    Mod,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RendererBackend {
+    Window,
+    // software renderer drawing into an offscreen surface, SDL runs on the dummy video driver
+    Headless,
+}
+
+
+// texture filtering when drawn scaled
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScaleQuality {
+    // pixel art
+    Nearest,
+    Linear,
+    // anisotropic where supported, linear elsewhere
+    Best,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum FullscreenType {
     Off = 0,
     True = 0x00_00_00_01,
@@ -39,16 +82,42 @@ pub enum FullscreenType {
 
 // ================ structs
 
+// everything Renderer needs to open the window, built with chained setters:
+// RendererConfig::new("title").size(1600, 900).vsync(true).build()
+#[derive(Clone, Debug)]
+pub struct RendererConfig {
+    pub title: String,
+    pub width: i32,
+    pub height: i32,
+    pub backend: RendererBackend,
+    pub vsync: bool,
+    // hardware renderer, false forces the software one
+    pub accelerated: bool,
+    pub resizable: bool,
+    pub borderless: bool,
+    pub fullscreen: FullscreenType,
+    // resolution draw calls are made in, scaled to the window. None uses the window size
+    pub logical_size: Option<(i32, i32)>,
+    pub scale_quality: ScaleQuality,
+}
+
 
 pub struct Renderer {
     ekran: *mut c::SDL_Renderer,
     window: *mut c::SDL_Window,
+    surface: *mut c::SDL_Surface,
+    backend: RendererBackend,
+    // from RendererConfig, kept through window resizes. None follows the window size
+    logical_size: Option<(i32, i32)>,
 }
 
 pub struct FpsCapDeltaTime {
-    frame_delay: u64,
+    frame_delay: time::Duration,
     pub set_fps: f32,
     pub dt: f32,
+    // length of one simulation step, independent of the render frame rate
+    pub fixed_dt: f32,
+    accumulator: f32,
     cap_frame_start: time::Instant,
     last_time: time::Instant,
 }
@@ -57,6 +126,21 @@ pub struct Texture {
     raw: *mut c::SDL_Texture,
 }
 
+pub struct Surface {
+    raw: *mut c::SDL_Surface,
+}
+
+// borrowed from the Renderer that owns the SDL window
+pub struct Window<'a> {
+    raw: *mut c::SDL_Window,
+    _renderer: std::marker::PhantomData<&'a Renderer>,
+}
+
+#[derive(Clone, Copy)]
+pub struct DisplayMode {
+    raw: c::SDL_DisplayMode,
+}
+
 #[derive(Clone, Copy)]
 pub struct Rect {
     raw: c::SDL_Rect,
@@ -73,6 +157,7 @@ pub struct Vector2D {
     pub y: f32,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -80,6 +165,26 @@ pub struct Color {
     pub a: u8,
 }
 
+// SDL_RenderGeometry came with SDL 2.0.18, the bundled bindings are older
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Vertex {
+    position: c::SDL_FPoint,
+    color: c::SDL_Color,
+    tex_coord: c::SDL_FPoint,
+}
+
+extern "C" {
+    fn SDL_RenderGeometry(
+        renderer: *mut c::SDL_Renderer,
+        texture: *mut c::SDL_Texture,
+        vertices: *const Vertex,
+        num_vertices: i32,
+        indices: *const i32,
+        num_indices: i32,
+    ) -> i32;
+}
+
 // ================ impls
 
 impl RendererFlip{
@@ -92,36 +197,171 @@ impl RendererFlip{
     }
 }
 
+impl BlendMode {
+    pub fn raw(self) -> c::SDL_BlendMode {
+        match self {
+            BlendMode::None => c::SDL_BlendMode::SDL_BLENDMODE_NONE,
+            BlendMode::Blend => c::SDL_BlendMode::SDL_BLENDMODE_BLEND,
+            BlendMode::Add => c::SDL_BlendMode::SDL_BLENDMODE_ADD,
+            BlendMode::Mod => c::SDL_BlendMode::SDL_BLENDMODE_MOD,
+        }
+    }
+}
+
+impl RendererConfig {
+    pub const DEFAULT_WIDTH: i32 = 1280;
+    pub const DEFAULT_HEIGHT: i32 = 720;
+
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.trim_end_matches('\0').to_string(),
+            width: Self::DEFAULT_WIDTH,
+            height: Self::DEFAULT_HEIGHT,
+            backend: RendererBackend::Window,
+            vsync: false,
+            accelerated: true,
+            resizable: false,
+            borderless: false,
+            fullscreen: FullscreenType::Off,
+            logical_size: None,
+            scale_quality: ScaleQuality::Nearest,
+        }
+    }
+
+    pub fn size(self, width: i32, height: i32) -> Self {
+        Self { width, height, ..self }
+    }
+
+    pub fn backend(self, backend: RendererBackend) -> Self {
+        Self { backend, ..self }
+    }
+
+    pub fn headless(self) -> Self {
+        self.backend(RendererBackend::Headless)
+    }
+
+    pub fn vsync(self, vsync: bool) -> Self {
+        Self { vsync, ..self }
+    }
+
+    pub fn accelerated(self, accelerated: bool) -> Self {
+        Self { accelerated, ..self }
+    }
+
+    pub fn resizable(self, resizable: bool) -> Self {
+        Self { resizable, ..self }
+    }
+
+    pub fn borderless(self, borderless: bool) -> Self {
+        Self { borderless, ..self }
+    }
+
+    pub fn fullscreen(self, fullscreen: FullscreenType) -> Self {
+        Self { fullscreen, ..self }
+    }
+
+    pub fn logical_size(self, width: i32, height: i32) -> Self {
+        Self { logical_size: Some((width, height)), ..self }
+    }
+
+    pub fn scale_quality(self, scale_quality: ScaleQuality) -> Self {
+        Self { scale_quality, ..self }
+    }
+
+    pub fn build(&self) -> Result<Renderer, SDLErrs> {
+        return Renderer::with_config(self);
+    }
+
+    fn window_flags(&self) -> u32 {
+        let mut flags = self.fullscreen as u32;
+        if self.resizable {
+            flags |= c::SDL_WindowFlags::SDL_WINDOW_RESIZABLE as u32;
+        }
+        if self.borderless {
+            flags |= c::SDL_WindowFlags::SDL_WINDOW_BORDERLESS as u32;
+        }
+        return flags;
+    }
+
+    fn renderer_flags(&self) -> u32 {
+        let mut flags = if self.accelerated {
+            c::SDL_RendererFlags::SDL_RENDERER_ACCELERATED as u32
+        } else {
+            c::SDL_RendererFlags::SDL_RENDERER_SOFTWARE as u32
+        };
+        if self.vsync {
+            flags |= c::SDL_RendererFlags::SDL_RENDERER_PRESENTVSYNC as u32;
+        }
+        return flags;
+    }
+}
+
 impl Renderer {
     pub fn new(title: &str) -> Result<Self, SDLErrs> {
+        return Self::with_config(&RendererConfig::new(title));
+    }
+
+    pub fn new_headless() -> Result<Self, SDLErrs> {
+        return Self::with_config(&RendererConfig::new("").headless());
+    }
+
+    pub fn with_backend(title: &str, backend: RendererBackend) -> Result<Self, SDLErrs> {
+        return Self::with_config(&RendererConfig::new(title).backend(backend));
+    }
+
+    pub fn with_config(config: &RendererConfig) -> Result<Self, SDLErrs> {
+        let backend = config.backend;
         let ekran;
-        let window;
+        let mut window = std::ptr::null_mut();
+        let mut surface = std::ptr::null_mut();
         unsafe {
-            if c::SDL_Init(c::SDL_INIT_VIDEO) < 0 {
+            let quality = match config.scale_quality {
+                ScaleQuality::Nearest => c"0",
+                ScaleQuality::Linear => c"1",
+                ScaleQuality::Best => c"2",
+            };
+            c::SDL_SetHint(c"SDL_RENDER_SCALE_QUALITY".as_ptr(), quality.as_ptr());
+
+            // headless picks the dummy driver itself, SDL_VIDEODRIVER would leak into later renderers
+            let init = match backend {
+                RendererBackend::Window => c::SDL_Init(c::SDL_INIT_VIDEO),
+                RendererBackend::Headless => {
+                    if c::SDL_Init(0) < 0 { -1 } else { c::SDL_VideoInit(c"dummy".as_ptr()) }
+                },
+            };
+            if init < 0 {
                 print_error!();
                 return Err(SDLErrs::InitializationErr);
             }
-            window = c::SDL_CreateWindow(
-                // cstr.as_ptr(),
-                title.as_ptr() as *const _,
-                c::SDL_WINDOWPOS_CENTERED_MASK as i32,
-                c::SDL_WINDOWPOS_CENTERED_MASK as i32,
-                WINDOW_WIDHT,
-                WINDOW_HEIGHT,
-                0
-            );
-            if window.is_null() {
-                print_error!();
-                return Err(SDLErrs::InitializationErr);
+
+            match backend {
+                RendererBackend::Window => {
+                    window = Self::create_window(config)?;
+                    ekran = c::SDL_CreateRenderer(window, -1, config.renderer_flags());
+                },
+                RendererBackend::Headless => {
+                    surface = c::SDL_CreateRGBSurfaceWithFormat(
+                        0,
+                        config.width,
+                        config.height,
+                        32,
+                        c::SDL_PixelFormatEnum::SDL_PIXELFORMAT_ARGB8888 as u32,
+                    );
+                    if surface.is_null() {
+                        print_error!();
+                        return Err(SDLErrs::CreateRGBSurfaceErr);
+                    }
+                    ekran = c::SDL_CreateSoftwareRenderer(surface);
+                },
             }
 
-            ekran = c::SDL_CreateRenderer(window, -1, c::SDL_RendererFlags::SDL_RENDERER_ACCELERATED as u32);
             if ekran.is_null() {
                 print_error!();
                 return Err(SDLErrs::InitializationErr);
             }
 
-            if c::SDL_RenderSetLogicalSize(ekran, WINDOW_WIDHT, WINDOW_HEIGHT) < 0 {
+            let (logical_w, logical_h) = config.logical_size.unwrap_or((config.width, config.height));
+            if c::SDL_RenderSetLogicalSize(ekran, logical_w, logical_h) < 0 {
                 print_error!();
                 return Err(SDLErrs::InitializationErr);
             }
@@ -142,37 +382,131 @@ impl Renderer {
         return Ok(Self {
             ekran,
             window,
+            surface,
+            backend,
+            logical_size: config.logical_size,
         });
     }
-    /*
-    pub fn set_window_fullscreen(&self, fullscreen_type: FullscreenType) -> Result<(), SDLErrs> {
+
+    fn create_window(config: &RendererConfig) -> Result<*mut c::SDL_Window, SDLErrs> {
+        let title = format!("{}\0", config.title);
         unsafe {
-            if c::SDL_SetWindowFullscreen(self.window, fullscreen_type as u32) < 0 {
+            let window = c::SDL_CreateWindow(
+                // cstr.as_ptr(),
+                title.as_ptr() as *const _,
+                c::SDL_WINDOWPOS_CENTERED_MASK as i32,
+                c::SDL_WINDOWPOS_CENTERED_MASK as i32,
+                config.width,
+                config.height,
+                config.window_flags()
+            );
+            if window.is_null() {
                 print_error!();
-                return Err(SDLErrs::WindowFullScreenErr);
+                return Err(SDLErrs::InitializationErr);
             }
+            return Ok(window);
         }
-        return Ok(());
     }
 
-    pub fn set_window_display_mode(&self, display_mode: &DisplayMode) -> Result<(), SDLErrs> {
+    pub fn backend(&self) -> RendererBackend {
+        self.backend
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.backend == RendererBackend::Headless
+    }
+
+    // None for the headless backend
+    pub fn window(&self) -> Option<Window<'_>> {
+        if self.window.is_null() {
+            return None;
+        }
+        return Some(Window { raw: self.window, _renderer: std::marker::PhantomData });
+    }
+
+    // size draw calls are made in: the logical size when one is set, the output size otherwise
+    pub fn screen_size(&self) -> (i32, i32) {
+        let mut w = 0;
+        let mut h = 0;
+        unsafe { c::SDL_RenderGetLogicalSize(self.ekran, &mut w, &mut h); }
+        if w == 0 || h == 0 {
+            return self.output_size();
+        }
+        return (w, h);
+    }
+
+    // blank ARGB8888 texture with alpha blending, filled through Texture::update
+    pub fn create_texture(&mut self, width: i32, height: i32) -> Result<Texture, SDLErrs> {
+        let tex = unsafe {
+            c::SDL_CreateTexture(
+                self.ekran,
+                c::SDL_PixelFormatEnum::SDL_PIXELFORMAT_ARGB8888 as u32,
+                c::SDL_TextureAccess::SDL_TEXTUREACCESS_STATIC as i32,
+                width,
+                height,
+            )
+        };
+        if tex.is_null() {
+            print_error!();
+            return Err(SDLErrs::CreateTextureErr);
+        }
+
+        unsafe { c::SDL_SetTextureBlendMode(tex, c::SDL_BlendMode::SDL_BLENDMODE_BLEND); }
+        return Ok(Texture { raw: tex });
+    }
+
+    // texture that can be drawn into with set_render_target
+    pub fn create_target_texture(&mut self, width: i32, height: i32) -> Result<Texture, SDLErrs> {
+        let tex = unsafe {
+            c::SDL_CreateTexture(
+                self.ekran,
+                c::SDL_PixelFormatEnum::SDL_PIXELFORMAT_ARGB8888 as u32,
+                c::SDL_TextureAccess::SDL_TEXTUREACCESS_TARGET as i32,
+                width,
+                height,
+            )
+        };
+        if tex.is_null() {
+            print_error!();
+            return Err(SDLErrs::CreateTextureErr);
+        }
+        return Ok(Texture { raw: tex });
+    }
+
+    // None draws to the window (or the headless surface) again
+    pub fn set_render_target(&mut self, target: Option<&Texture>) -> Result<(), SDLErrs> {
+        let raw = target.map_or(std::ptr::null_mut(), |t| t.raw);
         unsafe {
-            if c::SDL_SetWindowDisplayMode(self.window, &display_mode.raw) < 0 {
+            if c::SDL_SetRenderTarget(self.ekran, raw) < 0 {
                 print_error!();
-                return Err(SDLErrs::DisplayModeErr);
+                return Err(SDLErrs::RenderTargetErr);
             }
         }
         return Ok(());
     }
 
-    pub fn set_window_size(&self, width: i32, height: i32) {
-        unsafe { c::SDL_SetWindowSize(self.window, width, height); }
+    // size of the current render target in pixels, ignoring the logical size
+    pub fn output_size(&self) -> (i32, i32) {
+        let mut w = 0;
+        let mut h = 0;
+        unsafe { c::SDL_GetRendererOutputSize(self.ekran, &mut w, &mut h); }
+        return (w, h);
     }
 
-    pub fn set_window_bordered(&self, bordered: bool) {
-        unsafe { c::SDL_SetWindowBordered(self.window, if bordered { c::SDL_bool::SDL_TRUE } else { c::SDL_bool::SDL_FALSE }); }
+    // resolution the window is drawn at and scaled from, 0x0 draws in real output pixels
+    pub fn configured_logical_size(&self) -> Option<(i32, i32)> {
+        self.logical_size
+    }
+
+    pub fn set_logical_size(&mut self, width: i32, height: i32) -> Result<(), SDLErrs> {
+        unsafe {
+            if c::SDL_RenderSetLogicalSize(self.ekran, width, height) < 0 {
+                print_error!();
+                return Err(SDLErrs::RenderErr);
+            }
+        }
+        return Ok(());
     }
-    */
 
     pub fn load_texture(&mut self, filename: &str) -> Result<Texture, SDLErrs> {
         let tex = unsafe { c::image::IMG_LoadTexture(self.ekran, filename.as_ptr() as *const _) };
@@ -301,9 +635,36 @@ impl Renderer {
         }
     }
 
+    // blend mode of draw_* and fill_* calls
+    pub fn set_draw_blend_mode(&self, mode: BlendMode) {
+        unsafe {
+            if c::SDL_SetRenderDrawBlendMode(self.ekran, mode.raw()) < 0 {
+                print_error!();
+            }
+        }
+    }
+
+    pub fn draw_color(&self) -> Color {
+        let mut c = Color { r: 0, g: 0, b: 0, a: 0 };
+        unsafe { c::SDL_GetRenderDrawColor(self.ekran, &mut c.r, &mut c.g, &mut c.b, &mut c.a); }
+        return c;
+    }
+
+    // every draw_* and fill_* call uses the color set by set_draw_color
+
     pub fn draw_rect(&self, rect: &Rect) -> Result<(), SDLErrs> {
         unsafe {
             if c::SDL_RenderDrawRect(self.ekran, &rect.raw) < 0 {
+                print_error!();
+                return Err(SDLErrs::DrawRectErr);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn fill_rect(&self, rect: &Rect) -> Result<(), SDLErrs> {
+        unsafe {
+            if c::SDL_RenderFillRect(self.ekran, &rect.raw) < 0 {
                 print_error!();
                 return Err(SDLErrs::FillRectFaild);
             }
@@ -311,6 +672,116 @@ impl Renderer {
         return Ok(());
     }
 
+    pub fn draw_line(&self, from: Point2D, to: Point2D) -> Result<(), SDLErrs> {
+        unsafe {
+            if c::SDL_RenderDrawLine(self.ekran, from.x(), from.y(), to.x(), to.y()) < 0 {
+                print_error!();
+                return Err(SDLErrs::DrawLineErr);
+            }
+        }
+        return Ok(());
+    }
+
+    // connected line segments, pass the first point again at the end to close the shape
+    pub fn draw_lines(&self, points: &[Vector2D]) -> Result<(), SDLErrs> {
+        let raw = points.iter().map(|p| c::SDL_FPoint { x: p.x, y: p.y }).collect::<Vec<_>>();
+        unsafe {
+            if c::SDL_RenderDrawLinesF(self.ekran, raw.as_ptr(), raw.len() as i32) < 0 {
+                print_error!();
+                return Err(SDLErrs::DrawLineErr);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn draw_point(&self, point: Point2D) -> Result<(), SDLErrs> {
+        unsafe {
+            if c::SDL_RenderDrawPoint(self.ekran, point.x(), point.y()) < 0 {
+                print_error!();
+                return Err(SDLErrs::DrawPointErr);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn draw_points(&self, points: &[Point2D]) -> Result<(), SDLErrs> {
+        let raw = points.iter().map(|p| p.raw).collect::<Vec<_>>();
+        unsafe {
+            if c::SDL_RenderDrawPoints(self.ekran, raw.as_ptr(), raw.len() as i32) < 0 {
+                print_error!();
+                return Err(SDLErrs::DrawPointErr);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn draw_circle(&self, center: &Vector2D, radius: f32) -> Result<(), SDLErrs> {
+        return self.draw_ellipse(center, radius, radius);
+    }
+
+    pub fn fill_circle(&self, center: &Vector2D, radius: f32) -> Result<(), SDLErrs> {
+        return self.fill_ellipse(center, radius, radius);
+    }
+
+    pub fn draw_ellipse(&self, center: &Vector2D, radius_x: f32, radius_y: f32) -> Result<(), SDLErrs> {
+        let mut points = ellipse_points(center, radius_x, radius_y);
+        points.push(points[0].clone());
+        return self.draw_lines(&points);
+    }
+
+    pub fn fill_ellipse(&self, center: &Vector2D, radius_x: f32, radius_y: f32) -> Result<(), SDLErrs> {
+        return self.fill_polygon(&ellipse_points(center, radius_x, radius_y));
+    }
+
+    // convex polygon, points in order around the outline in either direction
+    pub fn fill_polygon(&self, points: &[Vector2D]) -> Result<(), SDLErrs> {
+        if points.len() < 3 {
+            return Ok(());
+        }
+        let color = self.draw_color().as_raw();
+        let vertices = points
+            .iter()
+            .map(|p| Vertex { position: c::SDL_FPoint { x: p.x, y: p.y }, color, tex_coord: c::SDL_FPoint { x: 0.0, y: 0.0 } })
+            .collect::<Vec<_>>();
+        // triangle fan around the first point
+        let indices = (1..points.len() as i32 - 1).flat_map(|i| [0, i, i + 1]).collect::<Vec<_>>();
+
+        unsafe {
+            let ret = SDL_RenderGeometry(
+                self.ekran,
+                std::ptr::null_mut(),
+                vertices.as_ptr(),
+                vertices.len() as i32,
+                indices.as_ptr(),
+                indices.len() as i32,
+            );
+            if ret < 0 {
+                print_error!();
+                return Err(SDLErrs::RenderGeometryErr);
+            }
+        }
+        return Ok(());
+    }
+
+    // pixels of the current render target, call before present
+    pub fn read_pixels(&self) -> Result<Surface, SDLErrs> {
+        let (w, h) = self.output_size();
+        let surface = Surface::new(w, h)?;
+        unsafe {
+            let raw = &*surface.raw;
+            if c::SDL_RenderReadPixels(self.ekran, std::ptr::null(), c::SDL_PixelFormatEnum::SDL_PIXELFORMAT_ARGB8888 as u32, raw.pixels, raw.pitch) < 0 {
+                print_error!();
+                return Err(SDLErrs::ReadPixelsErr);
+            }
+        }
+        return Ok(surface);
+    }
+
+    // saves what has been drawn this frame as a PNG, call before present
+    pub fn capture_frame(&self, path: &str) -> Result<(), SDLErrs> {
+        return self.read_pixels()?.save_png(path);
+    }
+
     pub fn clear(&self) {
         unsafe {
             if c::SDL_RenderClear(self.ekran) < 0 {
@@ -325,11 +796,20 @@ impl Renderer {
 }
 
 impl FpsCapDeltaTime {
+    // frames longer than this are clamped so a stall does not queue up hundreds of steps
+    const MAX_FRAME_TIME: f32 = 0.25;
+
     pub fn new(fps: u64) -> Self {
+        return Self::with_tick_rate(fps, 60);
+    }
+
+    pub fn with_tick_rate(fps: u64, tick_rate: u64) -> Self {
         Self {
-            frame_delay: (1000 / fps),
+            frame_delay: time::Duration::from_secs_f64(1.0 / fps as f64),
             set_fps: fps as f32,
             dt: 0.0,
+            fixed_dt: 1.0 / tick_rate as f32,
+            accumulator: 0.0,
             last_time: time::Instant::now(),
             cap_frame_start: time::Instant::now(),
         }
@@ -341,16 +821,256 @@ impl FpsCapDeltaTime {
         // println!("FPS: {} | set fps {} | dt {} ", 1.0 / self.dt, self.set_fps, self.dt);
         // println!("{}", (self.dt * self.set_fps));
         self.last_time = time::Instant::now();
+        self.accumulator += self.dt.min(Self::MAX_FRAME_TIME);
+    }
+
+    // returns true while there is enough accumulated time for another fixed step
+    pub fn step(&mut self) -> bool {
+        if self.accumulator >= self.fixed_dt {
+            self.accumulator -= self.fixed_dt;
+            return true;
+        }
+        return false;
+    }
+
+    // how far between the previous and the current simulation state the frame is, in 0..1
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.fixed_dt
     }
 
     pub fn end(&mut self) {
-        let cap_frame_end = self.cap_frame_start.elapsed().as_millis() as u64;
+        let cap_frame_end = self.cap_frame_start.elapsed();
         if cap_frame_end < self.frame_delay {
-            std::thread::sleep(time::Duration::from_millis(self.frame_delay - cap_frame_end));
+            std::thread::sleep(self.frame_delay - cap_frame_end);
         }
     }
 }
 
+impl Texture {
+    pub fn size(&self) -> (i32, i32) {
+        let mut w = 0;
+        let mut h = 0;
+        unsafe { c::SDL_QueryTexture(self.raw, std::ptr::null_mut(), std::ptr::null_mut(), &mut w, &mut h); }
+        return (w, h);
+    }
+
+    // multiplies the texture's colors when drawn, white leaves them unchanged. `color.a` is ignored
+    pub fn set_color_mod(&self, color: Color) {
+        unsafe { c::SDL_SetTextureColorMod(self.raw, color.r, color.g, color.b); }
+    }
+
+    // multiplies the texture's alpha when drawn, needs a blend mode other than None
+    pub fn set_alpha_mod(&self, alpha: u8) {
+        unsafe { c::SDL_SetTextureAlphaMod(self.raw, alpha); }
+    }
+
+    pub fn set_blend_mode(&self, mode: BlendMode) {
+        unsafe {
+            if c::SDL_SetTextureBlendMode(self.raw, mode.raw()) < 0 {
+                print_error!();
+            }
+        }
+    }
+
+    // copies `surface` into the `dst` area of the texture
+    pub fn update(&mut self, dst: Rect, surface: &Surface) -> Result<(), SDLErrs> {
+        let converted = surface.convert_argb8888()?;
+        unsafe {
+            let raw = &*converted.raw;
+            if c::SDL_UpdateTexture(self.raw, &dst.raw, raw.pixels, raw.pitch) < 0 {
+                print_error!();
+                return Err(SDLErrs::UpdateTextureErr);
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl Surface {
+    // blank ARGB8888
+    pub fn new(width: i32, height: i32) -> Result<Self, SDLErrs> {
+        let raw = unsafe {
+            c::SDL_CreateRGBSurfaceWithFormat(0, width, height, 32, c::SDL_PixelFormatEnum::SDL_PIXELFORMAT_ARGB8888 as u32)
+        };
+        if raw.is_null() {
+            print_error!();
+            return Err(SDLErrs::CreateRGBSurfaceErr);
+        }
+        return Ok(Self { raw });
+    }
+
+    // any format SDL_image reads, converted to ARGB8888. `path` without the trailing nul
+    pub fn load(path: &str) -> Result<Self, SDLErrs> {
+        let path = format!("{}\0", path.trim_end_matches('\0'));
+        let loaded = unsafe { Self::from_raw(c::image::IMG_Load(path.as_ptr() as *const _)) };
+        match loaded {
+            Some(surface) => return surface.convert_argb8888(),
+            None => {
+                print_error!();
+                return Err(SDLErrs::LoadSurfaceErr);
+            },
+        }
+    }
+
+    // ARGB8888 surfaces only, e.g. from new, load or Renderer::read_pixels
+    pub fn pixel(&self, x: i32, y: i32) -> Color {
+        let argb = unsafe { *self.pixel_ptr(x, y) };
+        return Color { a: (argb >> 24) as u8, r: (argb >> 16) as u8, g: (argb >> 8) as u8, b: argb as u8 };
+    }
+
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let argb = (color.a as u32) << 24 | (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32;
+        unsafe { *self.pixel_ptr(x, y) = argb; }
+    }
+
+    unsafe fn pixel_ptr(&self, x: i32, y: i32) -> *mut u32 {
+        let raw = &*self.raw;
+        assert!(x >= 0 && x < raw.w && y >= 0 && y < raw.h, "pixel ({}, {}) outside of the surface", x, y);
+        return (raw.pixels as *mut u8).offset((y * raw.pitch + x * 4) as isize) as *mut u32;
+    }
+
+    // `path` without the trailing nul
+    pub fn save_png(&self, path: &str) -> Result<(), SDLErrs> {
+        let path = format!("{}\0", path.trim_end_matches('\0'));
+        unsafe {
+            if c::image::IMG_SavePNG(self.raw, path.as_ptr() as *const _) < 0 {
+                print_error!();
+                return Err(SDLErrs::SaveImageErr);
+            }
+        }
+        return Ok(());
+    }
+
+    // takes ownership of a surface returned by SDL, SDL_ttf or SDL_image
+    pub unsafe fn from_raw(raw: *mut c::SDL_Surface) -> Option<Self> {
+        if raw.is_null() {
+            return None;
+        }
+        return Some(Self { raw });
+    }
+
+    pub fn width(&self) -> i32 {
+        unsafe { (*self.raw).w }
+    }
+
+    pub fn height(&self) -> i32 {
+        unsafe { (*self.raw).h }
+    }
+
+    pub fn convert_argb8888(&self) -> Result<Surface, SDLErrs> {
+        let raw = unsafe { c::SDL_ConvertSurfaceFormat(self.raw, c::SDL_PixelFormatEnum::SDL_PIXELFORMAT_ARGB8888 as u32, 0) };
+        if raw.is_null() {
+            print_error!();
+            return Err(SDLErrs::CreateRGBSurfaceErr);
+        }
+        return Ok(Surface { raw });
+    }
+}
+
+impl<'a> Window<'a> {
+    // Desktop keeps the desktop resolution, True switches the display to the window's display mode
+    pub fn set_fullscreen(&self, fullscreen_type: FullscreenType) -> Result<(), SDLErrs> {
+        unsafe {
+            if c::SDL_SetWindowFullscreen(self.raw, fullscreen_type as u32) < 0 {
+                print_error!();
+                return Err(SDLErrs::WindowFullScreenErr);
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        unsafe { c::SDL_GetWindowFlags(self.raw) & FullscreenType::True as u32 != 0 }
+    }
+
+    pub fn toggle_fullscreen(&self) -> Result<(), SDLErrs> {
+        return self.set_fullscreen(if self.is_fullscreen() { FullscreenType::Off } else { FullscreenType::Desktop });
+    }
+
+    // used by FullscreenType::True
+    pub fn set_display_mode(&self, display_mode: &DisplayMode) -> Result<(), SDLErrs> {
+        unsafe {
+            if c::SDL_SetWindowDisplayMode(self.raw, &display_mode.raw) < 0 {
+                print_error!();
+                return Err(SDLErrs::DisplayModeErr);
+            }
+        }
+        return Ok(());
+    }
+
+    // mode of the display the window is on
+    pub fn desktop_display_mode(&self) -> Result<DisplayMode, SDLErrs> {
+        let mut raw = std::mem::MaybeUninit::uninit();
+        unsafe {
+            let display = c::SDL_GetWindowDisplayIndex(self.raw);
+            if display < 0 || c::SDL_GetDesktopDisplayMode(display, raw.as_mut_ptr()) < 0 {
+                print_error!();
+                return Err(SDLErrs::DisplayModeErr);
+            }
+            return Ok(DisplayMode { raw: raw.assume_init() });
+        }
+    }
+
+    pub fn set_size(&self, width: i32, height: i32) {
+        unsafe { c::SDL_SetWindowSize(self.raw, width, height); }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        let mut w = 0;
+        let mut h = 0;
+        unsafe { c::SDL_GetWindowSize(self.raw, &mut w, &mut h); }
+        return (w, h);
+    }
+
+    pub fn set_bordered(&self, bordered: bool) {
+        unsafe { c::SDL_SetWindowBordered(self.raw, if bordered { c::SDL_bool::SDL_TRUE } else { c::SDL_bool::SDL_FALSE }); }
+    }
+
+    pub fn set_resizable(&self, resizable: bool) {
+        unsafe { c::SDL_SetWindowResizable(self.raw, if resizable { c::SDL_bool::SDL_TRUE } else { c::SDL_bool::SDL_FALSE }); }
+    }
+
+    // `title` without the trailing nul
+    pub fn set_title(&self, title: &str) {
+        let title = format!("{}\0", title.trim_end_matches('\0'));
+        unsafe { c::SDL_SetWindowTitle(self.raw, title.as_ptr() as *const _); }
+    }
+
+    // SDL keeps its own copy of the image
+    pub fn set_icon(&self, path: &str) -> Result<(), SDLErrs> {
+        let icon = Surface::load(path)?;
+        unsafe { c::SDL_SetWindowIcon(self.raw, icon.raw); }
+        return Ok(());
+    }
+}
+
+impl DisplayMode {
+    // refresh_rate 0 picks any
+    pub fn new(width: i32, height: i32, refresh_rate: i32) -> Self {
+        Self {
+            raw: c::SDL_DisplayMode {
+                format: 0,
+                w: width,
+                h: height,
+                refresh_rate,
+                driverdata: std::ptr::null_mut(),
+            },
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.raw.w
+    }
+
+    pub fn height(&self) -> i32 {
+        self.raw.h
+    }
+
+    pub fn refresh_rate(&self) -> i32 {
+        self.raw.refresh_rate
+    }
+}
+
 impl Rect {
     pub const fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
         Self { raw: c::SDL_Rect { x, y, w, h } }
@@ -444,9 +1164,29 @@ impl Vector2D {
             y
         }
     }
+
+    pub fn length(&self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    pub fn scale(&self, factor: f32) -> Self {
+        Self {
+            x: self.x * factor,
+            y: self.y * factor,
+        }
+    }
+
+    pub fn lerp(&self, other: &Vector2D, t: f32) -> Self {
+        Self {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+        }
+    }
 }
 
 impl Color {
+    pub const WHITE: Color = Color { r: 0xff, g: 0xff, b: 0xff, a: 0xff };
+
     pub fn as_raw(&self) -> c::SDL_Color {
         c::SDL_Color {
             r: self.r,
@@ -457,14 +1197,34 @@ impl Color {
     }
 }
 
+// about one segment per 6 pixels of outline
+fn ellipse_points(center: &Vector2D, radius_x: f32, radius_y: f32) -> Vec<Vector2D> {
+    let segments = ((radius_x.max(radius_y) * std::f32::consts::TAU / 6.0).ceil() as usize).clamp(12, 128);
+    return (0..segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            Vector2D::new(center.x + radius_x * angle.cos(), center.y + radius_y * angle.sin())
+        })
+        .collect();
+}
+
 
 impl Drop for Renderer {
     fn drop(&mut self) {
         unsafe {
-            c::SDL_DestroyWindow(self.window);
             c::SDL_DestroyRenderer(self.ekran);
+            if !self.window.is_null() {
+                c::SDL_DestroyWindow(self.window);
+            }
+            if !self.surface.is_null() {
+                c::SDL_FreeSurface(self.surface);
+            }
             c::ttf::TTF_Quit();
             c::image::IMG_Quit();
+            // started outside SDL_Init's subsystem count, SDL_Quit would leave it running
+            if self.backend == RendererBackend::Headless {
+                c::SDL_VideoQuit();
+            }
             c::SDL_Quit();
         }
     }
@@ -476,6 +1236,12 @@ impl Drop for Texture {
     }
 }
 
+impl Drop for Surface {
+    fn drop(&mut self) {
+        unsafe { c::SDL_FreeSurface(self.raw); }
+    }
+}
+
 
 
 impl From<(u8, u8, u8)> for Color {
@@ -0,0 +1,110 @@
+use bevy_ecs::prelude::*;
+use crate::ENGINE::camera::Camera2D;
+use crate::ENGINE::collision::collider_center;
+use crate::ENGINE::components::{AccelerationComp, ColliderComp, ColliderShape, MobComp, PlayerComp, SpriteComp};
+use crate::ENGINE::core::{Point2D, Rect, Renderer, SDLErrs, Vector2D};
+use crate::ENGINE::text::{TextAlign, TextStyle, UiFont};
+
+
+// gizmos drawn over the world, toggled with F3
+pub struct DebugOverlay {
+    pub enabled: bool,
+    // mobs closer than this to the player circle it instead of closing in
+    pub orbit_radius: f32,
+    // mobs closer than this to each other push apart
+    pub separation_radius: f32,
+    // line length in px per px/s^2 of steering acceleration
+    pub steering_scale: f32,
+}
+
+// everything the overlay draws something for
+type DebugBody<'a> = (
+    Entity,
+    &'a SpriteComp,
+    Option<&'a ColliderComp>,
+    Option<&'a AccelerationComp>,
+    Option<&'a MobComp>,
+    Option<&'a PlayerComp>,
+);
+
+
+impl DebugOverlay {
+    pub fn new(orbit_radius: f32, separation_radius: f32) -> Self {
+        Self {
+            enabled: false,
+            orbit_radius,
+            separation_radius,
+            steering_scale: 0.02,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+}
+
+
+// radii are centered where steering measures distances from, the movement position.
+// colliders are drawn around their own center
+pub fn debug_overlay_system(
+    mut core: NonSendMut<Renderer>,
+    mut font: Option<NonSendMut<UiFont>>,
+    overlay: Res<DebugOverlay>,
+    camera: Res<Camera2D>,
+    bodies: Query<DebugBody>,
+) -> Result<(), SDLErrs> {
+    if !overlay.enabled {
+        return Ok(());
+    }
+    for (entity, sprite, collider, accel, mob, player) in bodies.iter() {
+        let anchor = Vector2D::new(sprite.pos.x() as f32, sprite.pos.y() as f32);
+        let screen_anchor = camera.world_to_screen(&anchor);
+        let center = collider.map_or(anchor.clone(), |collider| collider_center(&anchor, collider));
+        let screen_center = camera.world_to_screen(&center);
+
+        if player.is_some() {
+            core.set_draw_color((0xf0, 0xd0, 0x30));
+            core.draw_circle(&screen_anchor, overlay.orbit_radius * camera.zoom)?;
+        }
+        if mob.is_some() {
+            core.set_draw_color((0x30, 0x80, 0xf0));
+            core.draw_circle(&screen_anchor, overlay.separation_radius * camera.zoom)?;
+        }
+
+        if let Some(collider) = collider {
+            core.set_draw_color((0x30, 0xf0, 0x60));
+            match collider.shape {
+                ColliderShape::Circle { radius } => core.draw_circle(&screen_center, radius * camera.zoom)?,
+                ColliderShape::Aabb { half_w, half_h } => {
+                    let world = Rect::new(
+                        (center.x - half_w) as i32,
+                        (center.y - half_h) as i32,
+                        (half_w * 2.0) as i32,
+                        (half_h * 2.0) as i32,
+                    );
+                    core.draw_rect(&camera.world_rect_to_screen(&world))?;
+                },
+            }
+        }
+
+        if let Some(accel) = accel {
+            let tip = Vector2D::new(
+                screen_center.x + accel.last_acceleration.x * overlay.steering_scale * camera.zoom,
+                screen_center.y + accel.last_acceleration.y * overlay.steering_scale * camera.zoom,
+            );
+            core.set_draw_color((0xf0, 0x40, 0x40));
+            core.draw_line(
+                Point2D::new(screen_center.x as i32, screen_center.y as i32),
+                Point2D::new(tip.x as i32, tip.y as i32),
+            )?;
+        }
+
+        if let Some(font) = font.as_mut() {
+            let style = TextStyle::new((0xff, 0xff, 0xff)).align(TextAlign::Center);
+            let label_y = camera.world_to_screen(&Vector2D::new(center.x, sprite.pos.y() as f32)).y as i32 - font.font.line_height();
+            core.draw_text(font.font.as_mut(), &entity.id().to_string(), screen_center.x as i32, label_y, &style)?;
+        }
+    }
+    core.set_draw_color((0, 0, 0));
+    return Ok(());
+}
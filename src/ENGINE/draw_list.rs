@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+use bevy_ecs::prelude::*;
+use crate::ENGINE::components::SpriteComp;
+
+
+// drawn back to front in this order, sprites without the component are Entities
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RenderLayer {
+    Background = 0,
+    // the only layer that is y-sorted
+    Entities = 1,
+    Effects = 2,
+    Ui = 3,
+}
+
+const LAYER_COUNT: usize = 4;
+
+// average moves per sprite the in-place repair of the entity order may spend before giving up
+const MAX_AVG_SHIFT: usize = 8;
+
+// sprites grouped by layer, kept between frames and only patched when sprites come and go.
+// the Entities layer is re-keyed and its order repaired in place every frame, see `repair_order`
+#[derive(Default)]
+pub struct DrawList {
+    layers: [Vec<(Entity, i32)>; LAYER_COUNT],
+    layer_of: HashMap<Entity, RenderLayer>,
+    removed: HashSet<Entity>,
+}
+
+impl RenderLayer {
+    pub const ALL: [RenderLayer; LAYER_COUNT] = [RenderLayer::Background, RenderLayer::Entities, RenderLayer::Effects, RenderLayer::Ui];
+}
+
+impl DrawList {
+    pub fn insert(&mut self, entity: Entity, layer: RenderLayer, key: i32) {
+        if let Some(old) = self.layer_of.insert(entity, layer) {
+            if old == layer {
+                return;
+            }
+            self.layers[old as usize].retain(|(e, _)| *e != entity);
+        }
+        self.layers[layer as usize].push((entity, key));
+    }
+
+    // removals are batched and applied in one pass by `flush_removed`
+    pub fn remove(&mut self, entity: Entity) {
+        if self.layer_of.remove(&entity).is_some() {
+            self.removed.insert(entity);
+        }
+    }
+
+    pub fn flush_removed(&mut self) {
+        if self.removed.is_empty() {
+            return;
+        }
+        for layer in self.layers.iter_mut() {
+            layer.retain(|(e, _)| !self.removed.contains(e));
+        }
+        self.removed.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.layer_of.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layer_of.is_empty()
+    }
+
+    // back to front
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.layers.iter().flat_map(|layer| layer.iter().map(|(e, _)| *e))
+    }
+
+    pub fn layer(&self, layer: RenderLayer) -> impl Iterator<Item = Entity> + '_ {
+        self.layers[layer as usize].iter().map(|(e, _)| *e)
+    }
+
+    fn sort_entities<F: FnMut(Entity) -> Option<i32>>(&mut self, mut key_of: F) {
+        let layer = &mut self.layers[RenderLayer::Entities as usize];
+        for entry in layer.iter_mut() {
+            if let Some(key) = key_of(entry.0) {
+                entry.1 = key;
+            }
+        }
+
+        repair_order(layer);
+    }
+}
+
+
+// insertion sort, O(n + moves) and stable for the few neighbours sprites pass each frame.
+// a shuffled order, e.g. a whole wave appended at once, runs out of moves and falls back to an
+// unstable sort. neither allocates
+fn repair_order(layer: &mut [(Entity, i32)]) {
+    let mut budget = layer.len() * MAX_AVG_SHIFT;
+    for i in 1..layer.len() {
+        let entry = layer[i];
+        let mut j = i;
+        while j > 0 && layer[j - 1].1 > entry.1 {
+            if budget == 0 {
+                layer[j] = entry;
+                layer.sort_unstable_by_key(|(_, key)| *key);
+                return;
+            }
+            budget -= 1;
+            layer[j] = layer[j - 1];
+            j -= 1;
+        }
+        layer[j] = entry;
+    }
+}
+
+
+fn sort_key(sprite: &SpriteComp) -> i32 {
+    sprite.pos.y() + sprite.width() / 2
+}
+
+pub fn update_draw_list_system(
+    mut draw_list: ResMut<DrawList>,
+    added: Query<(Entity, &SpriteComp, Option<&RenderLayer>), Added<SpriteComp>>,
+    relayered: Query<(Entity, &SpriteComp, &RenderLayer), Changed<RenderLayer>>,
+    removed_sprites: RemovedComponents<SpriteComp>,
+    removed_layers: RemovedComponents<RenderLayer>,
+    sprites: Query<&SpriteComp>,
+) {
+    for entity in removed_sprites.iter() {
+        draw_list.remove(entity);
+    }
+    draw_list.flush_removed();
+
+    for (entity, sprite, layer) in added.iter() {
+        draw_list.insert(entity, layer.copied().unwrap_or(RenderLayer::Entities), sort_key(sprite));
+    }
+    for (entity, sprite, layer) in relayered.iter() {
+        draw_list.insert(entity, *layer, sort_key(sprite));
+    }
+    for entity in removed_layers.iter() {
+        if let Ok(sprite) = sprites.get(entity) {
+            draw_list.insert(entity, RenderLayer::Entities, sort_key(sprite));
+        }
+    }
+
+    draw_list.sort_entities(|entity| sprites.get(entity).ok().map(sort_key));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(keys: &[i32]) -> Vec<(Entity, i32)> {
+        keys.iter().enumerate().map(|(i, key)| (Entity::from_raw(i as u32), *key)).collect()
+    }
+
+    fn ids(layer: &[(Entity, i32)]) -> Vec<u32> {
+        layer.iter().map(|(e, _)| e.id()).collect()
+    }
+
+    #[test]
+    fn small_moves_are_repaired_in_place_keeping_ties_in_order() {
+        let mut entries = layer(&[0, 12, 10, 20, 20, 31, 30, 40]);
+        repair_order(&mut entries);
+        assert_eq!(ids(&entries), vec![0, 2, 1, 3, 4, 6, 5, 7]);
+    }
+
+    #[test]
+    fn shuffled_order_falls_back_to_a_full_sort() {
+        // reversed needs n^2 / 2 moves, far past the budget
+        let keys = (0..200).rev().collect::<Vec<_>>();
+        let mut entries = layer(&keys);
+        repair_order(&mut entries);
+        assert!(entries.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert_eq!(entries.len(), 200);
+        let mut seen = ids(&entries);
+        seen.sort_unstable();
+        assert_eq!(seen, (0..200).collect::<Vec<_>>());
+    }
+}
@@ -14,6 +14,8 @@ pub fn poll_iter() -> Option<WEvent> {
 pub const QUIT: u32 = c::SDL_EventType::SDL_QUIT as u32;
 pub const MOUSE_BUTTON_UP: u32 = c::SDL_EventType::SDL_MOUSEBUTTONUP as u32;
 pub const MOUSE_MOTION: u32 = c::SDL_EventType::SDL_MOUSEMOTION as u32;
+pub const KEY_DOWN: u32 = c::SDL_EventType::SDL_KEYDOWN as u32;
+pub const WINDOW_EVENT: u32 = c::SDL_EventType::SDL_WINDOWEVENT as u32;
 
 pub mod ScanCode {
     use super::c;
@@ -22,6 +24,9 @@ pub mod ScanCode {
     pub const S: u32 = c::SDL_Scancode::SDL_SCANCODE_S as u32;
     pub const A: u32 = c::SDL_Scancode::SDL_SCANCODE_A as u32;
     pub const D: u32 = c::SDL_Scancode::SDL_SCANCODE_D as u32;
+    pub const F3: u32 = c::SDL_Scancode::SDL_SCANCODE_F3 as u32;
+    pub const F11: u32 = c::SDL_Scancode::SDL_SCANCODE_F11 as u32;
+    pub const F12: u32 = c::SDL_Scancode::SDL_SCANCODE_F12 as u32;
 }
 
 pub struct WEvent {
@@ -34,6 +39,26 @@ impl WEvent {
         unsafe { self.raw.type_ }
     }
 
+    // KEY_DOWN events only
+    pub fn scancode(&self) -> u32 {
+        unsafe { self.raw.key.keysym.scancode as u32 }
+    }
+
+    // KEY_DOWN sent again while the key is held
+    pub fn is_repeat(&self) -> bool {
+        unsafe { self.raw.key.repeat != 0 }
+    }
+
+    // new drawable size for WINDOW_EVENT events that changed it, by the user or by the program
+    pub fn resized_to(&self) -> Option<(i32, i32)> {
+        unsafe {
+            if self.raw.type_ != WINDOW_EVENT || self.raw.window.event != c::SDL_WindowEventID::SDL_WINDOWEVENT_SIZE_CHANGED as u8 {
+                return None;
+            }
+            return Some((self.raw.window.data1, self.raw.window.data2));
+        }
+    }
+
     pub fn get_mouse_pos(&self) -> (i32, i32) {
         unsafe {
             (self.raw.button.x, self.raw.button.y)
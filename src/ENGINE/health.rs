@@ -0,0 +1,159 @@
+use bevy_ecs::prelude::*;
+use crate::ENGINE::components::{ColliderComp, ContactDamageComp, FadeOutComp, HealthComp, MobComp, VelocityComp};
+use crate::ENGINE::core::{FpsCapDeltaTime, Vector2D};
+
+
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub amount: i32,
+    // added to the target's velocity when the hit lands
+    pub knockback: Vector2D,
+}
+
+// sent once, on the step the entity's health reaches zero
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub killer: Option<Entity>,
+}
+
+
+pub fn health_system(
+    fps_ctrl: Res<FpsCapDeltaTime>,
+    mut damage: EventReader<DamageEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut healths: Query<(&mut HealthComp, Option<&mut VelocityComp>)>,
+) {
+    let dt = fps_ctrl.fixed_dt;
+    for (mut health, _) in healths.iter_mut() {
+        if health.invulnerable > 0.0 {
+            health.invulnerable = (health.invulnerable - dt).max(0.0);
+        }
+    }
+
+    for hit in damage.iter() {
+        let (mut health, vel) = match healths.get_mut(hit.target) {
+            Ok(target) => target,
+            Err(_) => continue,
+        };
+        if health.is_dead() || health.invulnerable > 0.0 {
+            continue;
+        }
+
+        health.hp -= hit.amount;
+        health.invulnerable = health.iframes;
+        if let Some(mut vel) = vel {
+            vel.velocity.x += hit.knockback.x;
+            vel.velocity.y += hit.knockback.y;
+        }
+
+        if health.is_dead() {
+            deaths.send(DeathEvent { entity: hit.target, killer: hit.source });
+        }
+    }
+}
+
+// dead mobs stop steering and colliding, then fade out and are despawned by fade_out_system
+pub fn despawn_dead_mobs_system(mut commands: Commands, mut deaths: EventReader<DeathEvent>, mobs: Query<(), With<MobComp>>) {
+    for death in deaths.iter() {
+        if mobs.contains(death.entity) {
+            commands.entity(death.entity)
+                .remove::<MobComp>()
+                .remove::<ColliderComp>()
+                .remove::<ContactDamageComp>()
+                .insert(FadeOutComp::new(0.4));
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ENGINE::assets::Handle;
+    use crate::ENGINE::collision::ContactEvent;
+    use crate::ENGINE::components::{ColliderShape, SpriteComp};
+    use crate::ENGINE::core::{Point2D, Rect};
+    use crate::ENGINE::systems::fixed_schedule;
+
+    fn world_with_mob() -> (World, Entity) {
+        let mut world = World::new();
+        // 8 steps per second keeps every time in the test exact
+        world.insert_resource(FpsCapDeltaTime::with_tick_rate(60, 8));
+        world.insert_resource(Events::<ContactEvent>::default());
+        world.insert_resource(Events::<DamageEvent>::default());
+        world.insert_resource(Events::<DeathEvent>::default());
+        let mob = world.spawn()
+            .insert(SpriteComp::new(Handle::new(0), Rect::new(0, 0, 16, 16), Point2D::new(0, 0)))
+            .insert(VelocityComp { velocity: Vector2D::def() })
+            .insert(ColliderComp { shape: ColliderShape::Circle { radius: 8.0 }, offset: Vector2D::def() })
+            .insert(ContactDamageComp { damage: 1, knockback: 0.0 })
+            .insert(HealthComp::new(2, 0.25))
+            .insert(MobComp { rotate_dir: Vector2D::def() })
+            .id();
+        return (world, mob);
+    }
+
+    fn hit(world: &mut World, target: Entity) {
+        world.resource_mut::<Events<DamageEvent>>().send(DamageEvent {
+            target,
+            source: None,
+            amount: 1,
+            knockback: Vector2D::new(10.0, 0.0),
+        });
+    }
+
+    // events sent during the last step
+    fn deaths(world: &World) -> Vec<Entity> {
+        let events = world.resource::<Events<DeathEvent>>();
+        return events.get_reader().iter(events).map(|death| death.entity).collect();
+    }
+
+    #[test]
+    fn damaged_mob_dies_fades_and_is_despawned() {
+        let (mut world, mob) = world_with_mob();
+        let mut schedule = fixed_schedule();
+
+        hit(&mut world, mob);
+        schedule.run(&mut world);
+        assert_eq!(world.get::<HealthComp>(mob).unwrap().hp, 1);
+        assert_eq!(world.get::<VelocityComp>(mob).unwrap().velocity.x, 10.0);
+
+        // 0.125s into the 0.25s of i-frames
+        hit(&mut world, mob);
+        schedule.run(&mut world);
+        assert_eq!(world.get::<HealthComp>(mob).unwrap().hp, 1);
+        assert!(deaths(&world).is_empty());
+
+        schedule.run(&mut world);
+        hit(&mut world, mob);
+        schedule.run(&mut world);
+        assert!(world.get::<HealthComp>(mob).unwrap().is_dead());
+        assert_eq!(deaths(&world), vec![mob]);
+        assert!(world.get::<MobComp>(mob).is_none());
+        assert!(world.get::<ColliderComp>(mob).is_none());
+        assert!(world.get::<ContactDamageComp>(mob).is_none());
+        assert!(world.get::<FadeOutComp>(mob).is_some());
+
+        // the dead do not die twice
+        schedule.run(&mut world);
+        schedule.run(&mut world);
+        hit(&mut world, mob);
+        schedule.run(&mut world);
+        assert!(deaths(&world).is_empty());
+
+        // 0.4s of fading, three steps of it are gone already
+        let mut last_alpha = world.get::<SpriteComp>(mob).unwrap().alpha;
+        assert!(last_alpha < 0xff);
+        for _ in 0..3 {
+            if world.get_entity(mob).is_none() {
+                break;
+            }
+            let alpha = world.get::<SpriteComp>(mob).unwrap().alpha;
+            assert!(alpha <= last_alpha);
+            last_alpha = alpha;
+            schedule.run(&mut world);
+        }
+        assert!(world.get_entity(mob).is_none());
+    }
+}
@@ -1,3 +1,17 @@
 pub mod core;
 pub mod events;
-pub mod components;
\ No newline at end of file
+pub mod components;
+pub mod spatial;
+pub mod systems;
+pub mod collision;
+pub mod health;
+pub mod animation;
+pub mod sprite_sheet;
+pub mod assets;
+pub mod camera;
+pub mod draw_list;
+pub mod text;
+pub mod debug;
+pub mod canvas;
+#[cfg(test)]
+mod render_tests;
\ No newline at end of file
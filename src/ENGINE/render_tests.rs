@@ -0,0 +1,171 @@
+// golden-image tests: small scenes are drawn by the software renderer into a RenderCanvas
+// and compared with the PNGs in tests/golden. on a mismatch the frame and a diff image are
+// written to target/golden. run with UPDATE_GOLDEN=1 to write new references instead
+use std::sync::Mutex;
+use bevy_ecs::prelude::*;
+use crate::ENGINE::assets::AssetServer;
+use crate::ENGINE::camera::Camera2D;
+use crate::ENGINE::canvas::RenderCanvas;
+use crate::ENGINE::components::SpriteComp;
+use crate::ENGINE::core::{Color, Point2D, Rect, Renderer, RendererFlip, Surface};
+use crate::ENGINE::draw_list::{update_draw_list_system, DrawList};
+use crate::ENGINE::systems::{render_sprites_system, store_sdl_err, FrameStage, LastSDLErr, RenderStats};
+
+
+const GOLDEN_DIR: &str = "./tests/golden";
+const OUT_DIR: &str = "./target/golden";
+// per channel difference that still counts as equal
+const CHANNEL_TOLERANCE: i32 = 8;
+// share of pixels allowed to differ by more than that
+const MAX_BAD_PIXELS: f32 = 0.005;
+
+// SDL is initialized and shut down by every Renderer, tests must not overlap
+static SDL_LOCK: Mutex<()> = Mutex::new(());
+
+
+// fixed LCG so the scene never changes with the rand crate's version
+struct SceneRng(u64);
+
+impl SceneRng {
+    fn next(&mut self, below: u64) -> i32 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        return ((self.0 >> 33) % below) as i32;
+    }
+}
+
+// runs the draw list and sprite render pass once and reads the canvas back
+fn render_scene(width: i32, height: i32, spawn: impl FnOnce(&mut World, &mut Renderer, &mut AssetServer)) -> Surface {
+    let _guard = SDL_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut core = Renderer::new_headless().expect("headless renderer");
+    let mut assets = AssetServer::new();
+    let canvas = RenderCanvas::new(&mut core, width, height).expect("canvas");
+
+    let mut world = World::new();
+    spawn(&mut world, &mut core, &mut assets);
+    world.insert_resource(Camera2D::new(width, height));
+    world.insert_resource(DrawList::default());
+    world.insert_resource(RenderStats::default());
+    world.insert_resource(LastSDLErr(None));
+    world.insert_non_send_resource(core);
+    world.insert_non_send_resource(assets);
+    world.insert_non_send_resource(canvas);
+
+    let mut schedule = Schedule::default();
+    schedule
+        .add_stage(FrameStage::DrawList, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::DrawList, FrameStage::Render, SystemStage::single_threaded())
+        .add_system_to_stage(FrameStage::DrawList, update_draw_list_system)
+        .add_system_to_stage(FrameStage::Render, render_sprites_system.chain(store_sdl_err));
+    schedule.run(&mut world);
+    if let Some(err) = world.resource_mut::<LastSDLErr>().0.take() {
+        panic!("render failed: {:?}", err);
+    }
+
+    // still targeting the canvas
+    let frame = world.non_send_resource::<Renderer>().read_pixels().expect("read pixels");
+
+    world.remove_non_send_resource::<RenderCanvas>();
+    world.non_send_resource_mut::<AssetServer>().clear();
+    world.remove_non_send_resource::<Renderer>();
+    return frame;
+}
+
+fn spawn_sprite(world: &mut World, core: &mut Renderer, assets: &mut AssetServer, path: &str, srs: Rect, x: i32, y: i32) -> Entity {
+    let texture = assets.load_texture(core, path).expect("test texture");
+    return world.spawn().insert(SpriteComp::new(texture, srs, Point2D::new(x, y))).id();
+}
+
+fn assert_golden(name: &str, frame: &Surface) {
+    let golden_path = format!("{}/{}.png", GOLDEN_DIR, name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+        frame.save_png(&golden_path).expect("write golden image");
+        return;
+    }
+
+    let golden = Surface::load(&golden_path).unwrap_or_else(|_| panic!("missing {}, run with UPDATE_GOLDEN=1", golden_path));
+    assert_eq!(
+        (frame.width(), frame.height()),
+        (golden.width(), golden.height()),
+        "{}: frame size differs from the golden image", name,
+    );
+
+    let mut diff = Surface::new(frame.width(), frame.height()).unwrap();
+    let mut bad = 0;
+    for y in 0..frame.height() {
+        for x in 0..frame.width() {
+            let a = frame.pixel(x, y);
+            let b = golden.pixel(x, y);
+            let delta = [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)]
+                .iter()
+                .map(|(a, b)| (*a as i32 - *b as i32).abs())
+                .max()
+                .unwrap_or(0);
+            if delta > CHANNEL_TOLERANCE {
+                bad += 1;
+                diff.set_pixel(x, y, Color { r: 0xff, g: 0, b: 0xff, a: 0xff });
+            } else {
+                // matching pixels dimmed so the differences stand out
+                diff.set_pixel(x, y, Color { r: b.r / 4, g: b.g / 4, b: b.b / 4, a: 0xff });
+            }
+        }
+    }
+
+    let allowed = (frame.width() * frame.height()) as f32 * MAX_BAD_PIXELS;
+    if bad as f32 > allowed {
+        std::fs::create_dir_all(OUT_DIR).unwrap();
+        let actual_path = format!("{}/{}.actual.png", OUT_DIR, name);
+        let diff_path = format!("{}/{}.diff.png", OUT_DIR, name);
+        frame.save_png(&actual_path).unwrap();
+        diff.save_png(&diff_path).unwrap();
+        panic!("{}: {} pixels differ from {}, see {} and {}", name, bad, golden_path, actual_path, diff_path);
+    }
+}
+
+
+#[test]
+fn renderer_copy_draws_sheet_frames_and_scaled_textures() {
+    let frame = render_scene(96, 56, |world, core, assets| {
+        // green_bandit from the sheet, 1:1
+        spawn_sprite(world, core, assets, "./assets/sprites.png", Rect::new(0, 0, 48, 48), 2, 2);
+        // whole 16x16 texture at 2x
+        let bandit = spawn_sprite(world, core, assets, "./assets/bandit.png", Rect::new(0, 0, 16, 16), 56, 4);
+        world.entity_mut(bandit).get_mut::<SpriteComp>().unwrap().scale = 2;
+        // partly outside the canvas
+        spawn_sprite(world, core, assets, "./assets/weapon_spear.png", Rect::new(0, 0, 30, 6), 80, 46);
+    });
+    assert_golden("renderer_copy", &frame);
+}
+
+#[test]
+fn flipped_sprites_are_mirrored() {
+    let frame = render_scene(80, 28, |world, core, assets| {
+        let flips = [RendererFlip::None, RendererFlip::Horizontal, RendererFlip::Vertical];
+        for (i, flip) in flips.iter().enumerate() {
+            let entity = spawn_sprite(world, core, assets, "./assets/fish_idle.png", Rect::new(0, 0, 24, 24), 2 + i as i32 * 26, 2);
+            world.entity_mut(entity).get_mut::<SpriteComp>().unwrap().flip = *flip;
+        }
+    });
+    assert_golden("flips", &frame);
+}
+
+#[test]
+fn overlapping_sprites_are_drawn_in_y_order() {
+    let frame = render_scene(64, 64, |world, core, assets| {
+        let mut rng = SceneRng(0x5eed);
+        let mut keys = Vec::new();
+        for i in 0..8 {
+            let (path, size) = if i % 2 == 0 { ("./assets/gree_bandit.png", 16) } else { ("./assets/fish_bandit.png", 24) };
+            // ties avoided on purpose, the image should only depend on the y order and not on spawn order
+            let (x, y) = loop {
+                let (x, y) = (rng.next(48), rng.next(48));
+                if !keys.contains(&(y + size / 2)) {
+                    keys.push(y + size / 2);
+                    break (x, y);
+                }
+            };
+            spawn_sprite(world, core, assets, path, Rect::new(0, 0, size, size), x, y);
+        }
+    });
+    assert_golden("y_sort", &frame);
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use bevy_ecs::prelude::Entity;
+use crate::ENGINE::core::Vector2D;
+
+
+// uniform grid broad-phase, entities are re-registered every tick
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(Entity, Vector2D)>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell_of(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+    }
+
+    // keeps the cell vectors around so the allocations are reused next tick
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: &Vector2D) {
+        let key = self.cell_of(position.x, position.y);
+        self.cells.entry(key).or_default().push((entity, position.clone()));
+    }
+
+    // every registered entity within `radius` of `position`, including the one at `position` itself
+    pub fn query<'a>(&'a self, position: &Vector2D, radius: f32) -> impl Iterator<Item = &'a (Entity, Vector2D)> + 'a {
+        let (min_x, min_y) = self.cell_of(position.x - radius, position.y - radius);
+        let (max_x, max_y) = self.cell_of(position.x + radius, position.y + radius);
+        let (px, py) = (position.x, position.y);
+        let radius_squared = radius * radius;
+
+        return (min_x..=max_x)
+            .flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(move |key| self.cells.get(&key))
+            .flat_map(|cell| cell.iter())
+            .filter(move |(_, pos)| {
+                let x = pos.x - px;
+                let y = pos.y - py;
+                x * x + y * y <= radius_squared
+            });
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::Value;
+use crate::ENGINE::animation::{AnimationClip, AnimationFrame, AnimationSet, PlayMode};
+use crate::ENGINE::assets::Handle;
+use crate::ENGINE::core::{Rect, SDLErrs, Texture};
+
+
+// used when the metadata has no per-frame duration (TexturePacker, grid without "duration")
+const DEFAULT_FRAME_MS: f64 = 100.0;
+
+
+// uniform grid, frames are numbered left to right, top to bottom
+#[derive(Clone, Copy, Debug)]
+pub struct GridSpec {
+    pub cell_w: i32,
+    pub cell_h: i32,
+    pub columns: i32,
+    pub rows: i32,
+    // offset of the first cell from the image's top left
+    pub margin: i32,
+    // gap between cells
+    pub spacing: i32,
+}
+
+// frame rectangles and animation tags of one sheet, independent of the texture
+pub struct SheetMeta {
+    rects: Vec<Rect>,
+    durations: Vec<f32>,
+    by_name: HashMap<String, usize>,
+    clips: Arc<AnimationSet>,
+}
+
+// loaded through AssetServer::load_sheet
+pub struct SpriteSheet {
+    pub texture: Handle<Texture>,
+    pub meta: SheetMeta,
+}
+
+
+impl GridSpec {
+    pub fn cell(&self, column: i32, row: i32) -> Rect {
+        Rect::new(
+            self.margin + column * (self.cell_w + self.spacing),
+            self.margin + row * (self.cell_h + self.spacing),
+            self.cell_w,
+            self.cell_h,
+        )
+    }
+}
+
+impl SheetMeta {
+    pub fn from_grid(grid: &GridSpec) -> Self {
+        let mut meta = Self::empty();
+        for row in 0..grid.rows {
+            for column in 0..grid.columns {
+                let index = row * grid.columns + column;
+                meta.push_frame(index.to_string(), grid.cell(column, row), DEFAULT_FRAME_MS);
+            }
+        }
+        return meta;
+    }
+
+    // picks the parser from the file's contents: a "grid" object, or Aseprite / TexturePacker "frames"
+    pub fn load(path: &str) -> Result<Self, SDLErrs> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("can not read sprite sheet metadata {}: {}", path, err);
+                return Err(SDLErrs::LoadSheetMetaErr);
+            },
+        };
+        return Self::parse(&text);
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SDLErrs> {
+        let json: Value = match serde_json::from_str(text) {
+            Ok(json) => json,
+            Err(err) => {
+                eprintln!("sprite sheet metadata is not valid JSON: {}", err);
+                return Err(SDLErrs::LoadSheetMetaErr);
+            },
+        };
+
+        let meta = if json.get("grid").is_some() {
+            Self::parse_grid(&json)
+        } else {
+            Self::parse_packed(&json)
+        };
+        if meta.is_none() {
+            eprintln!("sprite sheet metadata needs a \"grid\" or \"frames\" object with well-formed entries");
+        }
+        return meta.ok_or(SDLErrs::LoadSheetMetaErr);
+    }
+
+    pub fn frame(&self, name: &str) -> Option<Rect> {
+        self.by_name.get(name).and_then(|&i| self.rects.get(i).copied())
+    }
+
+    pub fn frame_at(&self, index: usize) -> Option<Rect> {
+        self.rects.get(index).copied()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn clip(&self, tag: &str) -> Option<&AnimationClip> {
+        self.clips.get(tag)
+    }
+
+    // shareable with AnimationComp::new
+    pub fn clips(&self) -> Arc<AnimationSet> {
+        self.clips.clone()
+    }
+
+    fn empty() -> Self {
+        Self {
+            rects: Vec::new(),
+            durations: Vec::new(),
+            by_name: HashMap::new(),
+            clips: Arc::new(AnimationSet::new()),
+        }
+    }
+
+    fn push_frame(&mut self, name: String, rect: Rect, duration_ms: f64) {
+        self.by_name.insert(name, self.rects.len());
+        self.rects.push(rect);
+        self.durations.push((duration_ms / 1000.0) as f32);
+    }
+
+    fn frame_for_clip(&self, index: usize, duration_ms: Option<f64>) -> Option<AnimationFrame> {
+        Some(AnimationFrame {
+            srs: *self.rects.get(index)?,
+            duration: duration_ms.map_or(self.durations[index], |ms| (ms / 1000.0) as f32),
+        })
+    }
+
+    // {"grid": {"cell_w", "cell_h", "columns", "rows", "margin"?, "spacing"?},
+    //  "frames"?: {"name": [column, row]},
+    //  "animations"?: {"tag": {"frames": ["name" | index], "duration"?: ms, "mode"?: "loop" | "once" | "pingpong"}}}
+    fn parse_grid(json: &Value) -> Option<Self> {
+        let grid = json.get("grid")?;
+        let field = |name: &str| grid.get(name).and_then(Value::as_i64).map(|v| v as i32);
+        let spec = GridSpec {
+            cell_w: field("cell_w")?,
+            cell_h: field("cell_h")?,
+            columns: field("columns")?,
+            rows: field("rows")?,
+            margin: field("margin").unwrap_or(0),
+            spacing: field("spacing").unwrap_or(0),
+        };
+        let mut meta = Self::from_grid(&spec);
+
+        if let Some(names) = json.get("frames").and_then(Value::as_object) {
+            for (name, cell) in names {
+                let column = cell.get(0).and_then(Value::as_i64)? as i32;
+                let row = cell.get(1).and_then(Value::as_i64)? as i32;
+                if !(0..spec.columns).contains(&column) || !(0..spec.rows).contains(&row) {
+                    eprintln!("sprite sheet frame {:?} at [{}, {}] is outside the {}x{} grid", name, column, row, spec.columns, spec.rows);
+                    return None;
+                }
+                meta.by_name.insert(name.clone(), (row * spec.columns + column) as usize);
+            }
+        }
+
+        let mut clips = AnimationSet::new();
+        if let Some(animations) = json.get("animations").and_then(Value::as_object) {
+            for (tag, anim) in animations {
+                let duration = anim.get("duration").and_then(Value::as_f64);
+                let mode = parse_mode(anim.get("mode").and_then(Value::as_str).unwrap_or("loop"));
+                let mut frames = Vec::new();
+                for frame in anim.get("frames").and_then(Value::as_array)? {
+                    let index = match frame {
+                        Value::String(name) => *meta.by_name.get(name)?,
+                        _ => frame.as_u64()? as usize,
+                    };
+                    frames.push(meta.frame_for_clip(index, duration)?);
+                }
+                clips.insert(tag.clone(), AnimationClip::new(frames, mode));
+            }
+        }
+        meta.clips = Arc::new(clips);
+        return Some(meta);
+    }
+
+    // Aseprite and TexturePacker JSON, both "hash" and "array" exports:
+    // frames with {"frame": {x, y, w, h}, "duration"?}, Aseprite tags in "meta.frameTags",
+    // TexturePacker (pixi/phaser) tags in "animations": {"tag": ["frame name", ...]}
+    fn parse_packed(json: &Value) -> Option<Self> {
+        let mut entries: Vec<(String, &Value)> = match json.get("frames")? {
+            Value::Array(frames) => frames
+                .iter()
+                .map(|f| (f.get("filename").and_then(Value::as_str).unwrap_or("").to_string(), f))
+                .collect(),
+            Value::Object(frames) => {
+                let mut entries = frames.iter().map(|(name, f)| (name.clone(), f)).collect::<Vec<_>>();
+                // object keys lose the export order, Aseprite names end with the frame number
+                entries.sort_by(|a, b| natural_cmp(&a.0, &b.0));
+                entries
+            },
+            _ => return None,
+        };
+
+        let mut meta = Self::empty();
+        for (i, (name, frame)) in entries.drain(..).enumerate() {
+            let rect = frame.get("frame")?;
+            let coord = |key: &str| rect.get(key).and_then(Value::as_i64).map(|v| v as i32);
+            let duration = frame.get("duration").and_then(Value::as_f64).unwrap_or(DEFAULT_FRAME_MS);
+            let name = if name.is_empty() { i.to_string() } else { name };
+            meta.push_frame(name, Rect::new(coord("x")?, coord("y")?, coord("w")?, coord("h")?), duration);
+        }
+
+        let mut clips = AnimationSet::new();
+        let tags = json.get("meta").and_then(|m| m.get("frameTags")).and_then(Value::as_array);
+        for tag in tags.into_iter().flatten() {
+            let name = tag.get("name").and_then(Value::as_str)?;
+            let from = tag.get("from").and_then(Value::as_u64)? as usize;
+            let to = tag.get("to").and_then(Value::as_u64)? as usize;
+            let direction = tag.get("direction").and_then(Value::as_str).unwrap_or("forward");
+
+            let mut frames = (from..=to).map(|i| meta.frame_for_clip(i, None)).collect::<Option<Vec<_>>>()?;
+            if direction.starts_with("reverse") || direction == "pingpong_reverse" {
+                frames.reverse();
+            }
+            let mode = if direction.starts_with("pingpong") { PlayMode::PingPong } else { PlayMode::Loop };
+            clips.insert(name.to_string(), AnimationClip::new(frames, mode));
+        }
+
+        if let Some(animations) = json.get("animations").and_then(Value::as_object) {
+            for (tag, names) in animations {
+                let mut frames = Vec::new();
+                for name in names.as_array()? {
+                    let index = *meta.by_name.get(name.as_str()?)?;
+                    frames.push(meta.frame_for_clip(index, None)?);
+                }
+                clips.insert(tag.clone(), AnimationClip::new(frames, PlayMode::Loop));
+            }
+        }
+        meta.clips = Arc::new(clips);
+        return Some(meta);
+    }
+}
+
+impl SpriteSheet {
+    pub fn frame(&self, name: &str) -> Option<Rect> {
+        self.meta.frame(name)
+    }
+}
+
+
+fn parse_mode(mode: &str) -> PlayMode {
+    match mode {
+        "once" => PlayMode::Once,
+        "pingpong" => PlayMode::PingPong,
+        _ => PlayMode::Loop,
+    }
+}
+
+// "walk 2" < "walk 10"
+fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return std::cmp::Ordering::Equal,
+            (None, Some(_)) => return std::cmp::Ordering::Less,
+            (Some(_), None) => return std::cmp::Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut n: u64 = 0;
+                    while let Some(d) = it.peek().and_then(|c| c.to_digit(10)) {
+                        n = n.saturating_mul(10).saturating_add(d as u64);
+                        it.next();
+                    }
+                    n
+                };
+                let order = take_number(&mut a).cmp(&take_number(&mut b));
+                if order != std::cmp::Ordering::Equal {
+                    return order;
+                }
+            },
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(rect: Rect) -> (i32, i32, i32, i32) {
+        (rect.left(), rect.top(), rect.width(), rect.height())
+    }
+
+    fn clip_bounds(meta: &SheetMeta, tag: &str) -> Vec<(i32, i32, i32, i32)> {
+        meta.clip(tag).unwrap().frames.iter().map(|f| bounds(f.srs)).collect()
+    }
+
+    #[test]
+    fn grid_frames_are_numbered_row_by_row() {
+        let meta = SheetMeta::parse(r#"{
+            "grid": {"cell_w": 16, "cell_h": 8, "columns": 3, "rows": 2, "margin": 1, "spacing": 2},
+            "frames": {"idle": [2, 1]},
+            "animations": {"walk": {"frames": [0, "idle"], "duration": 50, "mode": "pingpong"}}
+        }"#).unwrap();
+
+        assert_eq!(meta.frame_count(), 6);
+        assert_eq!(bounds(meta.frame_at(4).unwrap()), (19, 11, 16, 8));
+        assert_eq!(bounds(meta.frame("idle").unwrap()), (37, 11, 16, 8));
+        assert_eq!(bounds(meta.frame("5").unwrap()), (37, 11, 16, 8));
+
+        let walk = meta.clip("walk").unwrap();
+        assert_eq!(walk.mode, PlayMode::PingPong);
+        assert_eq!(walk.frames[1].duration, 0.05);
+        assert_eq!(clip_bounds(&meta, "walk"), vec![(1, 1, 16, 8), (37, 11, 16, 8)]);
+    }
+
+    #[test]
+    fn grid_names_outside_the_grid_are_rejected() {
+        let grid = r#""grid": {"cell_w": 16, "cell_h": 16, "columns": 2, "rows": 2}"#;
+        for cell in ["[2, 0]", "[0, 2]", "[5, 0]", "[-1, 0]", "[0, -1]"] {
+            let text = format!(r#"{{{}, "frames": {{"x": {}}}}}"#, grid, cell);
+            assert!(SheetMeta::parse(&text).is_err(), "{} was accepted", cell);
+        }
+    }
+
+    #[test]
+    fn aseprite_hash_export_is_sorted_by_frame_number() {
+        let meta = SheetMeta::parse(r#"{
+            "frames": {
+                "hero 10.aseprite": {"frame": {"x": 20, "y": 0, "w": 10, "h": 12}, "duration": 200},
+                "hero 2.aseprite": {"frame": {"x": 10, "y": 0, "w": 10, "h": 12}, "duration": 100},
+                "hero 1.aseprite": {"frame": {"x": 0, "y": 0, "w": 10, "h": 12}, "duration": 100}
+            },
+            "meta": {"frameTags": [
+                {"name": "run", "from": 0, "to": 2, "direction": "forward"},
+                {"name": "back", "from": 0, "to": 1, "direction": "reverse"}
+            ]}
+        }"#).unwrap();
+
+        assert_eq!(meta.frame_count(), 3);
+        assert_eq!(bounds(meta.frame_at(2).unwrap()), (20, 0, 10, 12));
+        assert_eq!(clip_bounds(&meta, "run"), vec![(0, 0, 10, 12), (10, 0, 10, 12), (20, 0, 10, 12)]);
+        assert_eq!(meta.clip("run").unwrap().frames[2].duration, 0.2);
+        assert_eq!(clip_bounds(&meta, "back"), vec![(10, 0, 10, 12), (0, 0, 10, 12)]);
+    }
+
+    #[test]
+    fn aseprite_array_export_keeps_file_order() {
+        let meta = SheetMeta::parse(r#"{
+            "frames": [
+                {"filename": "b", "frame": {"x": 8, "y": 0, "w": 8, "h": 8}},
+                {"filename": "a", "frame": {"x": 0, "y": 0, "w": 8, "h": 8}},
+                {"frame": {"x": 16, "y": 0, "w": 8, "h": 8}}
+            ],
+            "meta": {"frameTags": [{"name": "bob", "from": 0, "to": 2, "direction": "pingpong"}]}
+        }"#).unwrap();
+
+        assert_eq!(bounds(meta.frame("b").unwrap()), (8, 0, 8, 8));
+        assert_eq!(bounds(meta.frame("a").unwrap()), (0, 0, 8, 8));
+        // unnamed frames are named by their index
+        assert_eq!(bounds(meta.frame("2").unwrap()), (16, 0, 8, 8));
+        assert_eq!(meta.clip("bob").unwrap().mode, PlayMode::PingPong);
+        assert_eq!(meta.clip("bob").unwrap().frames[0].duration, 0.1);
+    }
+
+    #[test]
+    fn texturepacker_animations_reference_frame_names() {
+        let meta = SheetMeta::parse(r#"{
+            "frames": {
+                "player": {"frame": {"x": 48, "y": 48, "w": 48, "h": 51}},
+                "mob": {"frame": {"x": 0, "y": 0, "w": 48, "h": 48}}
+            },
+            "animations": {"idle": ["player", "mob"]}
+        }"#).unwrap();
+
+        assert_eq!(bounds(meta.frame("player").unwrap()), (48, 48, 48, 51));
+        assert_eq!(clip_bounds(&meta, "idle"), vec![(48, 48, 48, 51), (0, 0, 48, 48)]);
+        assert_eq!(meta.clip("idle").unwrap().mode, PlayMode::Loop);
+        assert!(meta.frame("missing").is_none());
+    }
+
+    #[test]
+    fn unknown_or_broken_metadata_is_an_error() {
+        assert!(SheetMeta::parse("not json").is_err());
+        assert!(SheetMeta::parse(r#"{"meta": {}}"#).is_err());
+        assert!(SheetMeta::parse(r#"{"frames": {"a": {"frame": {"x": 0}}}}"#).is_err());
+        assert!(SheetMeta::parse(r#"{"frames": {"a": {"frame": {"x": 0, "y": 0, "w": 8, "h": 8}}}, "animations": {"idle": ["b"]}}"#).is_err());
+    }
+}
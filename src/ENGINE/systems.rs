@@ -0,0 +1,261 @@
+use bevy_ecs::prelude::*;
+use crate::ENGINE::components::{AccelerationComp, FadeOutComp, MovementComp, SpeedComp, SpriteComp, VelocityComp};
+use crate::ENGINE::animation::animation_system;
+use crate::ENGINE::assets::{hot_reload_system, AssetServer};
+use crate::ENGINE::camera::{camera_follow_system, Camera2D};
+use crate::ENGINE::canvas::{upscale_canvas_system, RenderCanvas};
+use crate::ENGINE::debug::debug_overlay_system;
+use crate::ENGINE::draw_list::{update_draw_list_system, DrawList};
+use crate::ENGINE::collision::{player_mob_collision_system, ContactEvent};
+use crate::ENGINE::health::{despawn_dead_mobs_system, health_system, DamageEvent, DeathEvent};
+use crate::ENGINE::core::{FpsCapDeltaTime, Point2D, Rect, Renderer, SDLErrs};
+
+
+// ================ stages
+
+// run once per fixed simulation step
+#[derive(StageLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FixedStage {
+    Input,
+    Steering,
+    Broadphase,
+    Separation,
+    Integrate,
+    Collision,
+    Damage,
+    Health,
+    Death,
+}
+
+// run once per rendered frame
+#[derive(StageLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FrameStage {
+    Animate,
+    Interpolate,
+    Camera,
+    DrawList,
+    Render,
+    // drawn with the world, into the canvas when there is one
+    WorldOverlay,
+    Upscale,
+    // HUD, drawn in window pixels on top of the world
+    Ui,
+    Capture,
+    Present,
+}
+
+// ================ resources
+
+// first error returned by a fallible system, the main loop stops on it
+pub struct LastSDLErr(pub Option<SDLErrs>);
+
+// set by any system that wants the main loop to stop after the current frame
+pub struct AppExit(pub bool);
+
+// sprite counts of the last rendered frame
+#[derive(Default, Debug)]
+pub struct RenderStats {
+    pub drawn: u32,
+    // outside of the camera viewport
+    pub culled: u32,
+}
+
+// size screen-space drawing is laid out for, the renderer's logical size or its window size
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+// set to save the next frame, HUD included, to SCREENSHOT_DIR
+#[derive(Default)]
+pub struct ScreenshotRequest(pub bool);
+
+pub const SCREENSHOT_DIR: &str = "./screenshots";
+
+// ================ schedules
+
+pub fn fixed_schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule
+        .add_stage(FixedStage::Input, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Input, FixedStage::Steering, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Steering, FixedStage::Broadphase, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Broadphase, FixedStage::Separation, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Separation, FixedStage::Integrate, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Integrate, FixedStage::Collision, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Collision, FixedStage::Damage, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Damage, FixedStage::Health, SystemStage::single_threaded())
+        .add_stage_after(FixedStage::Health, FixedStage::Death, SystemStage::single_threaded())
+        .add_system_to_stage(FixedStage::Input, Events::<ContactEvent>::update_system)
+        .add_system_to_stage(FixedStage::Input, Events::<DamageEvent>::update_system)
+        .add_system_to_stage(FixedStage::Input, Events::<DeathEvent>::update_system)
+        .add_system_to_stage(FixedStage::Integrate, integrate_movement_system)
+        .add_system_to_stage(FixedStage::Collision, player_mob_collision_system)
+        .add_system_to_stage(FixedStage::Health, health_system)
+        .add_system_to_stage(FixedStage::Death, despawn_dead_mobs_system)
+        .add_system_to_stage(FixedStage::Death, fade_out_system);
+    return schedule;
+}
+
+pub fn frame_schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule
+        .add_stage(FrameStage::Animate, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::Animate, FrameStage::Interpolate, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::Interpolate, FrameStage::Camera, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::Camera, FrameStage::DrawList, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::DrawList, FrameStage::Render, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::Render, FrameStage::WorldOverlay, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::WorldOverlay, FrameStage::Upscale, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::Upscale, FrameStage::Ui, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::Ui, FrameStage::Capture, SystemStage::single_threaded())
+        .add_stage_after(FrameStage::Capture, FrameStage::Present, SystemStage::single_threaded())
+        .add_system_to_stage(FrameStage::Animate, hot_reload_system)
+        .add_system_to_stage(FrameStage::Animate, animation_system)
+        .add_system_to_stage(FrameStage::Interpolate, interpolate_sprites_system)
+        .add_system_to_stage(FrameStage::Camera, camera_follow_system)
+        .add_system_to_stage(FrameStage::DrawList, update_draw_list_system)
+        .add_system_to_stage(FrameStage::Render, render_sprites_system.chain(store_sdl_err))
+        .add_system_to_stage(FrameStage::WorldOverlay, debug_overlay_system.chain(store_sdl_err))
+        .add_system_to_stage(FrameStage::Upscale, upscale_canvas_system.chain(store_sdl_err))
+        .add_system_to_stage(FrameStage::Capture, screenshot_system)
+        .add_system_to_stage(FrameStage::Present, present_system);
+    return schedule;
+}
+
+// ================ systems
+
+pub fn store_sdl_err(In(result): In<Result<(), SDLErrs>>, mut last: ResMut<LastSDLErr>) {
+    if let Err(err) = result {
+        if last.0.is_none() {
+            last.0 = Some(err);
+        }
+    }
+}
+
+// semi-implicit euler: acceleration -> velocity -> position
+pub fn integrate_movement_system(
+    fps_ctrl: Res<FpsCapDeltaTime>,
+    mut bodies: Query<(&mut AccelerationComp, &mut VelocityComp, &mut MovementComp, &SpeedComp)>,
+) {
+    let dt = fps_ctrl.fixed_dt;
+    for (mut accel, mut vel, mut movement, speed) in bodies.iter_mut() {
+        vel.velocity = vel.velocity.scale((1.0 - speed.friction * dt).max(0.0));
+
+        let speed_before = vel.velocity.length();
+        vel.velocity.x += accel.acceleration.x * dt;
+        vel.velocity.y += accel.acceleration.y * dt;
+
+        let limit = speed.max_speed.max(speed_before);
+        let speed_after = vel.velocity.length();
+        if speed_after > limit {
+            vel.velocity = vel.velocity.scale(limit / speed_after);
+        }
+
+        movement.prev_position = movement.position.clone();
+        movement.position.x += vel.velocity.x * dt;
+        movement.position.y += vel.velocity.y * dt;
+
+        accel.last_acceleration = accel.acceleration.clone();
+        accel.acceleration.x = 0.0;
+        accel.acceleration.y = 0.0;
+    }
+}
+
+pub fn fade_out_system(
+    mut commands: Commands,
+    fps_ctrl: Res<FpsCapDeltaTime>,
+    mut fading: Query<(Entity, &mut FadeOutComp, &mut SpriteComp)>,
+) {
+    for (entity, mut fade, mut sprite) in fading.iter_mut() {
+        fade.remaining -= fps_ctrl.fixed_dt;
+        if fade.remaining <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        sprite.alpha = (fade.remaining / fade.duration * 255.0) as u8;
+    }
+}
+
+pub fn interpolate_sprites_system(fps_ctrl: Res<FpsCapDeltaTime>, mut sprites: Query<(&MovementComp, &mut SpriteComp)>) {
+    let alpha = fps_ctrl.alpha();
+    for (movement, mut sprite) in sprites.iter_mut() {
+        let pos = movement.prev_position.lerp(&movement.position, alpha);
+        sprite.pos.set_x(pos.x as i32);
+        sprite.pos.set_y(pos.y as i32);
+    }
+}
+
+pub fn render_sprites_system(
+    mut core: NonSendMut<Renderer>,
+    assets: NonSend<AssetServer>,
+    canvas: Option<NonSend<RenderCanvas>>,
+    camera: Res<Camera2D>,
+    draw_list: Res<DrawList>,
+    mut stats: ResMut<RenderStats>,
+    sprites: Query<&SpriteComp>,
+) -> Result<(), SDLErrs> {
+    if let Some(canvas) = canvas {
+        core.set_render_target(Some(&canvas.texture))?;
+    }
+    // core.set_draw_color((10, 10, 30));
+    core.clear();
+
+    let viewport = Rect::new(0, 0, camera.viewport_w, camera.viewport_h);
+    stats.drawn = 0;
+    stats.culled = 0;
+
+    // render all sprites, back to front
+    for sprite in draw_list.iter().filter_map(|e| sprites.get(e).ok()) {
+        let dst = camera.world_rect_to_screen(&Rect::new(sprite.pos.x(), sprite.pos.y(), sprite.width(), sprite.height()));
+        // a rotated sprite can reach past its rect by up to its longest side
+        let reach = if sprite.rotation != 0.0 { dst.width().max(dst.height()) } else { 0 };
+        let bounds = Rect::new(dst.left() - reach, dst.top() - reach, dst.width() + reach * 2, dst.height() + reach * 2);
+        if !viewport.has_intersection(&bounds) {
+            stats.culled += 1;
+            continue;
+        }
+        if let Some(texture) = assets.texture(sprite.texture) {
+            // textures are shared between sprites, so this is set before every copy
+            texture.set_color_mod(sprite.tint);
+            texture.set_alpha_mod(sprite.alpha);
+            texture.set_blend_mode(sprite.blend);
+            if sprite.is_transformed() {
+                // the pivot is in scaled sprite pixels, the camera may zoom them
+                let pivot = sprite.pivot.map(|p| Point2D::new(
+                    p.x() * dst.width() / sprite.width().max(1),
+                    p.y() * dst.height() / sprite.height().max(1),
+                ));
+                core.renderer_copy_ref_ex(texture, &sprite.srs, &dst, sprite.rotation, pivot.as_ref(), sprite.flip)?;
+            } else {
+                core.renderer_copy(texture, sprite.srs, dst)?;
+            }
+            stats.drawn += 1;
+        }
+    }
+    return Ok(());
+}
+
+// a failed screenshot is reported and the game goes on
+pub fn screenshot_system(core: NonSend<Renderer>, mut request: ResMut<ScreenshotRequest>) {
+    if !request.0 {
+        return;
+    }
+    request.0 = false;
+
+    if let Err(err) = std::fs::create_dir_all(SCREENSHOT_DIR) {
+        eprintln!("screenshot failed, can not create {}: {}", SCREENSHOT_DIR, err);
+        return;
+    }
+    let stamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_millis());
+    let path = format!("{}/screenshot-{}.png", SCREENSHOT_DIR, stamp);
+    match core.capture_frame(&path) {
+        Ok(()) => println!("saved {}", path),
+        Err(err) => eprintln!("screenshot failed: {:?}", err),
+    }
+}
+
+pub fn present_system(mut core: NonSendMut<Renderer>) {
+    core.present();
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use sdl2_sys as c;
+use crate::print_error;
+use crate::ENGINE::core::{Color, Rect, Renderer, SDLErrs, Surface, Texture};
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle {
+    pub color: Color,
+    // without a wrap width `x` is the left edge, the center or the right edge of every line;
+    // with one, lines are aligned inside the box from `x` to `x + wrap_width`
+    pub align: TextAlign,
+    pub wrap_width: Option<i32>,
+    // whole-pixel upscale, for pixel fonts
+    pub scale: i32,
+}
+
+// where a glyph sits in one of the font's page textures
+#[derive(Clone, Copy, Debug)]
+pub struct Glyph {
+    pub page: usize,
+    pub srs: Rect,
+    // pen movement after the glyph
+    pub advance: i32,
+}
+
+// anything draw_text can draw with, glyphs are white and tinted with TextStyle::color
+pub trait Font {
+    // None for characters the font does not have
+    fn glyph(&mut self, core: &mut Renderer, ch: char) -> Result<Option<Glyph>, SDLErrs>;
+    fn page(&self, page: usize) -> Option<&Texture>;
+    fn line_height(&self) -> i32;
+}
+
+// font used by the HUD and debug overlays, kept as a non-send resource
+pub struct UiFont {
+    pub font: Box<dyn Font>,
+    // TextStyle::scale the font is meant to be drawn at
+    pub scale: i32,
+}
+
+// glyphs rasterized on first use and packed into shared textures, row by row
+pub struct GlyphAtlas {
+    pages: Vec<Texture>,
+    page_size: i32,
+    cursor_x: i32,
+    cursor_y: i32,
+    row_h: i32,
+}
+
+pub struct TtfFont {
+    raw: *mut c::ttf::TTF_Font,
+    atlas: GlyphAtlas,
+    glyphs: HashMap<char, Option<Glyph>>,
+    line_skip: i32,
+}
+
+// monospaced glyph sheet, white glyphs on transparent cells laid out left to right, top to bottom.
+// needs no TTF file and is drawn pixel for pixel, use whole TextStyle::scale values to upscale it
+pub struct BitmapFont {
+    texture: Texture,
+    cell_w: i32,
+    cell_h: i32,
+    columns: i32,
+    // character in the first cell, the sheet covers a contiguous range from it
+    first: u32,
+    count: u32,
+    // drawn for characters the sheet does not have
+    fallback: Option<char>,
+}
+
+// 6x8 cells from ' ' to DEL, which is drawn as a box and used as the fallback
+pub const DEFAULT_BITMAP_FONT: &str = "./assets/font_6x8.png";
+
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            align: TextAlign::Left,
+            wrap_width: None,
+            scale: 1,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn new(color: impl Into<Color>) -> Self {
+        Self { color: color.into(), ..Self::default() }
+    }
+
+    pub fn align(self, align: TextAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    pub fn wrap(self, width: i32) -> Self {
+        Self { wrap_width: Some(width), ..self }
+    }
+
+    pub fn scale(self, scale: i32) -> Self {
+        Self { scale, ..self }
+    }
+}
+
+impl GlyphAtlas {
+    pub fn new(page_size: i32) -> Self {
+        Self {
+            pages: Vec::new(),
+            page_size,
+            cursor_x: 0,
+            cursor_y: 0,
+            row_h: 0,
+        }
+    }
+
+    pub fn page(&self, page: usize) -> Option<&Texture> {
+        self.pages.get(page)
+    }
+
+    pub fn insert(&mut self, core: &mut Renderer, surface: &Surface) -> Result<(usize, Rect), SDLErrs> {
+        // 1px gap so linear filtering never bleeds the neighbour in
+        let w = surface.width() + 1;
+        let h = surface.height() + 1;
+        if w > self.page_size || h > self.page_size {
+            return Err(SDLErrs::GlyphTooLargeErr);
+        }
+
+        if self.cursor_x + w > self.page_size {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_h;
+            self.row_h = 0;
+        }
+        if self.pages.is_empty() || self.cursor_y + h > self.page_size {
+            self.pages.push(core.create_texture(self.page_size, self.page_size)?);
+            self.cursor_x = 0;
+            self.cursor_y = 0;
+            self.row_h = 0;
+        }
+
+        let page = self.pages.len() - 1;
+        let rect = Rect::new(self.cursor_x, self.cursor_y, surface.width(), surface.height());
+        self.pages[page].update(rect, surface)?;
+
+        self.cursor_x += w;
+        self.row_h = self.row_h.max(h);
+        return Ok((page, rect));
+    }
+}
+
+impl TtfFont {
+    pub fn load(path: &str, pt_size: i32) -> Result<Self, SDLErrs> {
+        let raw = unsafe { c::ttf::TTF_OpenFont(format!("{}\0", path.trim_end_matches('\0')).as_ptr() as *const _, pt_size) };
+        if raw.is_null() {
+            print_error!();
+            return Err(SDLErrs::TTFFontErr);
+        }
+
+        return Ok(Self {
+            raw,
+            atlas: GlyphAtlas::new(512),
+            glyphs: HashMap::new(),
+            line_skip: unsafe { c::ttf::TTF_FontLineSkip(raw) },
+        });
+    }
+
+    fn rasterize(&mut self, core: &mut Renderer, ch: char) -> Result<Option<Glyph>, SDLErrs> {
+        // this SDL_ttf only takes UCS-2
+        if ch as u32 > 0xffff {
+            return Ok(None);
+        }
+        let code = ch as u16;
+        let mut advance = 0;
+        unsafe {
+            if c::ttf::TTF_GlyphIsProvided(self.raw, code) == 0 {
+                return Ok(None);
+            }
+            let (mut min_x, mut max_x, mut min_y, mut max_y) = (0, 0, 0, 0);
+            if c::ttf::TTF_GlyphMetrics(self.raw, code, &mut min_x, &mut max_x, &mut min_y, &mut max_y, &mut advance) < 0 {
+                print_error!();
+                return Err(SDLErrs::TTFQueryTextureErr);
+            }
+        }
+
+        let white = c::SDL_Color { r: 0xff, g: 0xff, b: 0xff, a: 0xff };
+        let surface = unsafe { Surface::from_raw(c::ttf::TTF_RenderGlyph_Blended(self.raw, code, white)) };
+        let glyph = match surface {
+            Some(surface) if surface.width() > 0 && surface.height() > 0 => {
+                let (page, srs) = self.atlas.insert(core, &surface)?;
+                Glyph { page, srs, advance }
+            },
+            // whitespace
+            _ => Glyph { page: 0, srs: Rect::new(0, 0, 0, 0), advance },
+        };
+        return Ok(Some(glyph));
+    }
+}
+
+impl Font for TtfFont {
+    fn glyph(&mut self, core: &mut Renderer, ch: char) -> Result<Option<Glyph>, SDLErrs> {
+        if let Some(glyph) = self.glyphs.get(&ch) {
+            return Ok(*glyph);
+        }
+        let glyph = self.rasterize(core, ch)?;
+        self.glyphs.insert(ch, glyph);
+        return Ok(glyph);
+    }
+
+    fn page(&self, page: usize) -> Option<&Texture> {
+        self.atlas.page(page)
+    }
+
+    fn line_height(&self) -> i32 {
+        self.line_skip
+    }
+}
+
+impl Drop for TtfFont {
+    fn drop(&mut self) {
+        unsafe { c::ttf::TTF_CloseFont(self.raw); }
+    }
+}
+
+impl BitmapFont {
+    pub fn load(core: &mut Renderer, path: &str, cell_w: i32, cell_h: i32, first: char) -> Result<Self, SDLErrs> {
+        let texture = core.load_texture(&format!("{}\0", path.trim_end_matches('\0')))?;
+        let (w, h) = texture.size();
+        let columns = (w / cell_w).max(1);
+        return Ok(Self {
+            texture,
+            cell_w,
+            cell_h,
+            columns,
+            first: first as u32,
+            count: (columns * (h / cell_h)) as u32,
+            fallback: None,
+        });
+    }
+
+    pub fn load_default(core: &mut Renderer) -> Result<Self, SDLErrs> {
+        return Ok(Self::load(core, DEFAULT_BITMAP_FONT, 6, 8, ' ')?.with_fallback('\x7f'));
+    }
+
+    pub fn with_fallback(self, fallback: char) -> Self {
+        Self { fallback: Some(fallback), ..self }
+    }
+
+    fn cell(&self, ch: char) -> Option<Glyph> {
+        let index = (ch as u32).checked_sub(self.first).filter(|&i| i < self.count)? as i32;
+        return Some(Glyph {
+            page: 0,
+            srs: Rect::new((index % self.columns) * self.cell_w, (index / self.columns) * self.cell_h, self.cell_w, self.cell_h),
+            advance: self.cell_w,
+        });
+    }
+}
+
+impl Font for BitmapFont {
+    fn glyph(&mut self, _core: &mut Renderer, ch: char) -> Result<Option<Glyph>, SDLErrs> {
+        return Ok(self.cell(ch).or_else(|| self.fallback.and_then(|f| self.cell(f))));
+    }
+
+    fn page(&self, page: usize) -> Option<&Texture> {
+        if page == 0 { Some(&self.texture) } else { None }
+    }
+
+    fn line_height(&self) -> i32 {
+        self.cell_h
+    }
+}
+
+
+impl Renderer {
+    // returns the size of the drawn block
+    pub fn draw_text(&mut self, font: &mut dyn Font, text: &str, x: i32, y: i32, style: &TextStyle) -> Result<(i32, i32), SDLErrs> {
+        let lines = self.layout_text(font, text, style)?;
+        let line_h = font.line_height() * style.scale;
+        let block_w = lines.iter().map(|(_, w)| *w).max().unwrap_or(0);
+
+        for (i, (line, line_w)) in lines.iter().enumerate() {
+            let mut pen_x = match (style.align, style.wrap_width) {
+                (TextAlign::Left, _) => x,
+                (TextAlign::Center, Some(box_w)) => x + (box_w - line_w) / 2,
+                (TextAlign::Right, Some(box_w)) => x + box_w - line_w,
+                (TextAlign::Center, None) => x - line_w / 2,
+                (TextAlign::Right, None) => x - line_w,
+            };
+            let pen_y = y + i as i32 * line_h;
+
+            for &ch in line {
+                let glyph = match font.glyph(self, ch)? {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+                if glyph.srs.width() > 0 {
+                    if let Some(page) = font.page(glyph.page) {
+                        page.set_color_mod(style.color);
+                        page.set_alpha_mod(style.color.a);
+                        let dst = Rect::new(pen_x, pen_y, glyph.srs.width() * style.scale, glyph.srs.height() * style.scale);
+                        self.renderer_copy(page, glyph.srs, dst)?;
+                    }
+                }
+                pen_x += glyph.advance * style.scale;
+            }
+        }
+        return Ok((block_w, lines.len() as i32 * line_h));
+    }
+
+    pub fn measure_text(&mut self, font: &mut dyn Font, text: &str, style: &TextStyle) -> Result<(i32, i32), SDLErrs> {
+        let lines = self.layout_text(font, text, style)?;
+        let block_w = lines.iter().map(|(_, w)| *w).max().unwrap_or(0);
+        return Ok((block_w, lines.len() as i32 * font.line_height() * style.scale));
+    }
+
+    // greedy word wrap, words longer than the wrap width are broken between characters
+    fn layout_text(&mut self, font: &mut dyn Font, text: &str, style: &TextStyle) -> Result<Vec<(Vec<char>, i32)>, SDLErrs> {
+        let mut lines = Vec::new();
+        let max_w = style.wrap_width.unwrap_or(i32::MAX);
+
+        for paragraph in text.split('\n') {
+            let mut line: Vec<char> = Vec::new();
+            let mut line_w = 0;
+
+            for (i, word) in paragraph.split(' ').enumerate() {
+                let space_w = if i > 0 { self.advance_of(font, ' ', style.scale)? } else { 0 };
+                let mut word_w = 0;
+                for ch in word.chars() {
+                    word_w += self.advance_of(font, ch, style.scale)?;
+                }
+
+                if !line.is_empty() && line_w + space_w + word_w > max_w {
+                    lines.push((std::mem::take(&mut line), line_w));
+                    line_w = 0;
+                } else if i > 0 {
+                    line.push(' ');
+                    line_w += space_w;
+                }
+
+                for ch in word.chars() {
+                    let ch_w = self.advance_of(font, ch, style.scale)?;
+                    if !line.is_empty() && line_w + ch_w > max_w && word_w > max_w {
+                        lines.push((std::mem::take(&mut line), line_w));
+                        line_w = 0;
+                    }
+                    line.push(ch);
+                    line_w += ch_w;
+                }
+            }
+            lines.push((line, line_w));
+        }
+        return Ok(lines);
+    }
+
+    fn advance_of(&mut self, font: &mut dyn Font, ch: char, scale: i32) -> Result<i32, SDLErrs> {
+        return Ok(font.glyph(self, ch)?.map_or(0, |g| g.advance * scale));
+    }
+}
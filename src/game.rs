@@ -0,0 +1,335 @@
+use bevy_ecs::prelude::*;
+use crate::ENGINE::collision::ContactEvent;
+use crate::ENGINE::collision::{collider_center, contact};
+use crate::ENGINE::components::{AccelerationComp, ColliderComp, ColliderShape, ContactDamageComp, HealthComp, MobComp, MovementComp, PlayerComp, SpeedComp, SpriteComp, VelocityComp, WeaponComp};
+use crate::ENGINE::core::{Color, FpsCapDeltaTime, Point2D, Rect, Renderer, RendererFlip, SDLErrs, Vector2D};
+use crate::ENGINE::events::{self, KeyboardState};
+use crate::ENGINE::health::{DamageEvent, DeathEvent};
+use crate::ENGINE::spatial::SpatialHash;
+use crate::ENGINE::systems::{store_sdl_err, AppExit, FixedStage, FrameStage, RenderStats, ScreenSize};
+use crate::ENGINE::text::{TextAlign, TextStyle, UiFont};
+
+
+pub const PLAYER_SPEED: SpeedComp = SpeedComp { max_speed: 190.0, thrust: 2400.0, friction: 10.0 };
+// orbit threshold around the player and mob separation radius
+pub const DIS_RADIUS: f32 = 40.0;
+
+
+// the level, twice the screen size and centered on the screen area the mobs spawn around
+pub fn world_bounds(screen: &ScreenSize) -> Rect {
+    Rect::new(-screen.width / 2, -screen.height / 2, screen.width * 2, screen.height * 2)
+}
+
+
+#[derive(Default)]
+pub struct Score {
+    pub kills: u32,
+}
+
+
+pub fn add_game_systems(schedule: &mut Schedule) {
+    schedule
+        .add_system_to_stage(FixedStage::Input, player_input_system)
+        .add_system_to_stage(FixedStage::Steering, mob_steering_system)
+        .add_system_to_stage(FixedStage::Broadphase, register_mobs_system)
+        .add_system_to_stage(FixedStage::Separation, mob_separation_system)
+        .add_system_to_stage(FixedStage::Damage, contact_damage_system)
+        .add_system_to_stage(FixedStage::Damage, weapon_hit_system)
+        .add_system_to_stage(FixedStage::Death, player_death_system)
+        .add_system_to_stage(FixedStage::Death, score_system)
+        .add_system_to_stage(FixedStage::Death, hit_blink_system)
+        .add_system_to_stage(FixedStage::Death, hit_flash_system)
+        .add_system_to_stage(FixedStage::Death, mob_facing_system);
+}
+
+pub fn add_frame_systems(schedule: &mut Schedule) {
+    schedule
+        // after the owner's sprite was interpolated
+        .add_system_to_stage(FrameStage::Camera, weapon_aim_system)
+        .add_system_to_stage(FrameStage::Ui, hud_system.chain(store_sdl_err));
+}
+
+
+pub fn player_input_system(keys: Res<KeyboardState<'static>>, mut pla_acceleration: Query<(&mut AccelerationComp, &SpeedComp), With<PlayerComp>>) {
+    for (mut accel, speed) in pla_acceleration.iter_mut() {
+        let mut dir = Vector2D::def();
+        if keys.is_scancode_pressed(events::ScanCode::A) {
+            dir.x = -1.0;
+        } else if keys.is_scancode_pressed(events::ScanCode::D) {
+            dir.x = 1.0;
+        }
+        if keys.is_scancode_pressed(events::ScanCode::W) {
+            dir.y = -1.0;
+        } else if keys.is_scancode_pressed(events::ScanCode::S) {
+            dir.y = 1.0;
+        }
+
+        if dir.x != 0.0 && dir.y != 0.0 {
+            dir.x *= std::f32::consts::FRAC_1_SQRT_2;
+            dir.y *= std::f32::consts::FRAC_1_SQRT_2;
+        }
+
+        accel.acceleration.x += dir.x * speed.thrust;
+        accel.acceleration.y += dir.y * speed.thrust;
+    }
+}
+
+// mob direction of movement and/or rotaion around player
+pub fn mob_steering_system(
+    player: Query<&MovementComp, With<PlayerComp>>,
+    mut update_mob: Query<(&mut AccelerationComp, &MobComp, &MovementComp, &SpeedComp)>,
+) {
+    const DIS: f32 = DIS_RADIUS * DIS_RADIUS;
+
+    let pla_position = match player.get_single() {
+        Ok(movement) => movement.position.clone(),
+        Err(_) => return,
+    };
+
+    for (mut dir1, mob1, movement1, speed1) in update_mob.iter_mut() {
+        let mut dir_x = pla_position.x - movement1.position.x;
+        let mut dir_y = pla_position.y - movement1.position.y;
+        let distance_squared = dir_x * dir_x + dir_y * dir_y;
+
+        let hyp = (dir_x * dir_x + dir_y * dir_y).sqrt();
+        // normalized
+        dir_x /= hyp;
+        dir_y /= hyp;
+
+        if DIS < distance_squared {
+            dir1.acceleration.x += dir_x * speed1.thrust;
+            dir1.acceleration.y += dir_y * speed1.thrust;
+        } else {
+            dir1.acceleration.x += (dir_y) * mob1.rotate_dir.x * speed1.thrust;
+            dir1.acceleration.y += (dir_x) * mob1.rotate_dir.y * speed1.thrust;
+        }
+    }
+}
+
+pub fn register_mobs_system(mut grid: ResMut<SpatialHash>, mobs: Query<(Entity, &MovementComp), With<MobComp>>) {
+    grid.clear();
+    for (entt, movement) in mobs.iter() {
+        grid.insert(entt, &movement.position);
+    }
+}
+
+// mob other mob collision
+pub fn mob_separation_system(grid: Res<SpatialHash>, mut mobs: Query<(&mut AccelerationComp, Entity, &MovementComp, &SpeedComp), With<MobComp>>) {
+    for (mut dir1, entt1, movement1, speed1) in mobs.iter_mut() {
+        // collition with other mob
+        for (entt2, position2) in grid.query(&movement1.position, DIS_RADIUS) {
+            if entt1 == *entt2 {
+                continue;
+            }
+
+            let mut x = movement1.position.x - position2.x;
+            let mut y = movement1.position.y - position2.y;
+            let dist_squered = x * x + y * y;
+            if dist_squered == 0.0 {
+                continue;
+            }
+
+            let hyp = dist_squered.sqrt();
+            x /= hyp;
+            y /= hyp;
+
+
+            dir1.acceleration.x += x * speed1.thrust;
+            dir1.acceleration.y += y * speed1.thrust;
+        }
+    }
+}
+
+// mobs touching the player hurt it and knock it back
+pub fn contact_damage_system(
+    mut contacts: EventReader<ContactEvent>,
+    mut damage: EventWriter<DamageEvent>,
+    players: Query<(), With<PlayerComp>>,
+    attackers: Query<&ContactDamageComp>,
+) {
+    for contact in contacts.iter() {
+        if !players.contains(contact.a) {
+            continue;
+        }
+        if let Ok(attacker) = attackers.get(contact.b) {
+            damage.send(DamageEvent {
+                target: contact.a,
+                source: Some(contact.b),
+                amount: attacker.damage,
+                // normal points from the player to the mob
+                knockback: contact.normal.scale(-attacker.knockback),
+            });
+        }
+    }
+}
+
+// the weapon's tip hurts every mob it touches, credited to the weapon's owner
+pub fn weapon_hit_system(
+    mut damage: EventWriter<DamageEvent>,
+    weapons: Query<(&WeaponComp, &SpriteComp, &ContactDamageComp)>,
+    mobs: Query<(Entity, &MovementComp, &ColliderComp), With<MobComp>>,
+) {
+    const TIP_RADIUS: f32 = 8.0;
+    for (weapon, sprite, attack) in weapons.iter() {
+        let pivot = sprite.pivot.unwrap_or_else(|| Point2D::new(sprite.width() / 2, sprite.height() / 2));
+        let reach = (sprite.width() - pivot.x()) as f32;
+        let angle = sprite.rotation.to_radians() as f32;
+        let tip = Vector2D::new(
+            (sprite.pos.x() + pivot.x()) as f32 + angle.cos() * reach,
+            (sprite.pos.y() + pivot.y()) as f32 + angle.sin() * reach,
+        );
+
+        for (mob, movement, collider) in mobs.iter() {
+            let center = collider_center(&movement.position, collider);
+            if let Some(hit) = contact(&tip, &ColliderShape::Circle { radius: TIP_RADIUS }, &center, &collider.shape) {
+                damage.send(DamageEvent {
+                    target: mob,
+                    source: Some(weapon.owner),
+                    amount: attack.damage,
+                    // normal points from the tip to the mob
+                    knockback: hit.normal.scale(attack.knockback),
+                });
+            }
+        }
+    }
+}
+
+pub fn player_death_system(mut deaths: EventReader<DeathEvent>, players: Query<(), With<PlayerComp>>, mut exit: ResMut<AppExit>) {
+    for death in deaths.iter() {
+        if players.contains(death.entity) {
+            exit.0 = true;
+        }
+    }
+}
+
+// blink while the i-frames last, hidden through alpha so the sprite keeps its size
+pub fn hit_blink_system(mut players: Query<(&mut SpriteComp, &HealthComp), With<PlayerComp>>) {
+    const BLINK_TIME: f32 = 0.08;
+    for (mut sprite, health) in players.iter_mut() {
+        let elapsed = health.iframes - health.invulnerable;
+        let hidden = health.invulnerable > 0.0 && (elapsed / BLINK_TIME) as i32 % 2 == 1;
+        let alpha = if hidden { 0 } else { 0xff };
+        if sprite.alpha != alpha {
+            sprite.alpha = alpha;
+        }
+    }
+}
+
+// mob art faces right, a small dead band keeps orbiting mobs from flickering
+pub fn mob_facing_system(mut mobs: Query<(&mut SpriteComp, &VelocityComp), With<MobComp>>) {
+    const TURN_SPEED: f32 = 10.0;
+    for (mut sprite, vel) in mobs.iter_mut() {
+        let flip = if vel.velocity.x < -TURN_SPEED {
+            RendererFlip::Horizontal
+        } else if vel.velocity.x > TURN_SPEED {
+            RendererFlip::None
+        } else {
+            continue;
+        };
+        if sprite.flip != flip {
+            sprite.flip = flip;
+        }
+    }
+}
+
+// sprite and collider, enough to find where a body's center is
+type Body<'a> = (&'a SpriteComp, Option<&'a ColliderComp>);
+
+// keeps the weapon's pivot on its owner's collider center, pointing at the nearest mob
+pub fn weapon_aim_system(
+    owners: Query<Body, Without<WeaponComp>>,
+    mobs: Query<Body, (With<MobComp>, Without<WeaponComp>)>,
+    mut weapons: Query<(&WeaponComp, &mut SpriteComp)>,
+) {
+    let center_of = |sprite: &SpriteComp, collider: Option<&ColliderComp>| {
+        let pos = Vector2D::new(sprite.pos.x() as f32, sprite.pos.y() as f32);
+        collider.map_or(pos.clone(), |collider| collider_center(&pos, collider))
+    };
+
+    for (weapon, mut sprite) in weapons.iter_mut() {
+        let hand = match owners.get(weapon.owner) {
+            Ok((owner, collider)) => center_of(owner, collider),
+            Err(_) => continue,
+        };
+
+        let target = mobs
+            .iter()
+            .map(|(mob, collider)| center_of(mob, collider))
+            .min_by(|a, b| {
+                let da = (a.x - hand.x).powi(2) + (a.y - hand.y).powi(2);
+                let db = (b.x - hand.x).powi(2) + (b.y - hand.y).powi(2);
+                da.total_cmp(&db)
+            });
+        if let Some(target) = target {
+            sprite.rotation = ((target.y - hand.y).atan2(target.x - hand.x) as f64).to_degrees();
+        }
+
+        let pivot = sprite.pivot.unwrap_or_else(|| Point2D::new(sprite.width() / 2, sprite.height() / 2));
+        sprite.pos.set_x(hand.x as i32 - pivot.x());
+        sprite.pos.set_y(hand.y as i32 - pivot.y());
+    }
+}
+
+// red for the first half of the i-frames, white for the rest
+pub fn hit_flash_system(mut sprites: Query<(&mut SpriteComp, &HealthComp)>) {
+    for (mut sprite, health) in sprites.iter_mut() {
+        let tint = if health.invulnerable > health.iframes / 2.0 {
+            Color { r: 0xff, g: 0x50, b: 0x50, a: 0xff }
+        } else {
+            Color::WHITE
+        };
+        if sprite.tint != tint {
+            sprite.tint = tint;
+        }
+    }
+}
+
+pub fn score_system(mut deaths: EventReader<DeathEvent>, players: Query<(), With<PlayerComp>>, mut score: ResMut<Score>) {
+    for death in deaths.iter() {
+        if !players.contains(death.entity) {
+            score.kills += 1;
+        }
+    }
+}
+
+// health and score on the left, debug info on the right
+pub fn hud_system(
+    mut core: NonSendMut<Renderer>,
+    font: Option<NonSendMut<UiFont>>,
+    fps_ctrl: Res<FpsCapDeltaTime>,
+    score: Res<Score>,
+    stats: Res<RenderStats>,
+    screen: Res<ScreenSize>,
+    player: Query<&HealthComp, With<PlayerComp>>,
+) -> Result<(), SDLErrs> {
+    let mut font = match font {
+        Some(font) => font,
+        None => return Ok(()),
+    };
+    let scale = font.scale;
+    let font = font.font.as_mut();
+
+    let hp = player.get_single().map_or(0, |health| health.hp);
+    let max_hp = player.get_single().map_or(0, |health| health.max_hp);
+    let style = TextStyle::new((0xff, 0xff, 0xff)).scale(scale);
+    let (text_w, line_h) = core.draw_text(font, &format!("HP {}/{}", hp, max_hp), 16, 12, &style)?;
+    core.draw_text(font, &format!("Kills {}", score.kills), 16, 12 + line_h, &style)?;
+
+    // health bar next to the HP text
+    let bar = Rect::new(16 + text_w + 8, 12 + line_h / 4, 120, line_h / 2);
+    core.set_draw_color((0x40, 0x10, 0x10));
+    core.fill_rect(&bar)?;
+    if max_hp > 0 {
+        core.set_draw_color((0xd0, 0x30, 0x30));
+        core.fill_rect(&Rect::new(bar.left(), bar.top(), bar.width() * hp.max(0) / max_hp, bar.height()))?;
+    }
+    core.set_draw_color((0xff, 0xff, 0xff));
+    core.draw_rect(&bar)?;
+    core.set_draw_color((0, 0, 0));
+
+    let fps = if fps_ctrl.dt > 0.0 { 1.0 / fps_ctrl.dt } else { 0.0 };
+    let debug = format!("{:.0} fps\n{} drawn / {} culled", fps, stats.drawn, stats.culled);
+    let debug_style = TextStyle::new((0xa0, 0xa0, 0xa0)).align(TextAlign::Right).scale(scale);
+    core.draw_text(font, &debug, screen.width - 16, 12, &debug_style)?;
+    return Ok(());
+}
@@ -1,198 +1,336 @@
 #![allow(non_snake_case)]
 
-use bevy_ecs::prelude::{Entity, With, Without, World};
+use bevy_ecs::prelude::{Events, Stage, World};
 use rand::Rng;
-use crate::ENGINE::components::{AccelerationComp, MobComp, MovementComp, PlayerComp, SpriteComp};
-use crate::ENGINE::core::{FpsCapDeltaTime, Point2D, Rect, Renderer, SDLErrs, Vector2D};
+use crate::ENGINE::assets::AssetServer;
+use crate::ENGINE::animation::AnimationComp;
+use crate::ENGINE::camera::Camera2D;
+use crate::ENGINE::canvas::RenderCanvas;
+use crate::ENGINE::collision::ContactEvent;
+use crate::ENGINE::debug::DebugOverlay;
+use crate::ENGINE::draw_list::DrawList;
+use crate::ENGINE::components::{AccelerationComp, ColliderComp, ColliderShape, ContactDamageComp, HealthComp, MobComp, MovementComp, PlayerComp, SheetSpriteComp, SpeedComp, SpriteComp, VelocityComp, WeaponComp};
+use crate::ENGINE::core::{FpsCapDeltaTime, Point2D, Rect, Renderer, RendererBackend, RendererConfig, SDLErrs, Vector2D};
 use crate::ENGINE::events;
+use crate::ENGINE::spatial::SpatialHash;
+use crate::ENGINE::health::{DamageEvent, DeathEvent};
+use crate::ENGINE::systems::{self, AppExit, LastSDLErr, RenderStats, ScreenSize, ScreenshotRequest};
+use crate::ENGINE::text::{BitmapFont, TtfFont, UiFont};
+use crate::game::{Score, DIS_RADIUS};
 
 mod ENGINE;
+mod game;
 
-pub const WINDOW_WIDHT: i32 = 1280;
-pub const WINDOW_HEIGHT: i32 = 720;
 
-
-fn random_pos_xy(i: i32) -> (i32, i32) {
+fn random_pos_xy(i: i32, screen: &ScreenSize) -> (i32, i32) {
     let rand_x = if i % 2 == 0 {
-        if rand::thread_rng().gen::<f32>() > 0.5 { -17 } else { 1280 + 17 }
+        if rand::thread_rng().gen::<f32>() > 0.5 { -17 } else { screen.width + 17 }
     } else {
-        rand::thread_rng().gen_range(-17..1280 + 17)
+        rand::thread_rng().gen_range(-17..screen.width + 17)
     };
     let rand_y = if i % 2 == 0 {
-        rand::thread_rng().gen_range(-17..720 + 17)
+        rand::thread_rng().gen_range(-17..screen.height + 17)
     } else {
-        if rand::thread_rng().gen::<f32>() > 0.5 { -17 } else { 720 + 17 }
+        if rand::thread_rng().gen::<f32>() > 0.5 { -17 } else { screen.height + 17 }
     };
 
     return (rand_x, rand_y);
 }
 
 
+// ./assets/font.ttf when one is shipped, the built in pixel font otherwise
+fn load_ui_font(core: &mut Renderer) -> Result<UiFont, SDLErrs> {
+    if std::path::Path::new("./assets/font.ttf").exists() {
+        if let Ok(font) = TtfFont::load("./assets/font.ttf", 18) {
+            return Ok(UiFont { font: Box::new(font), scale: 1 });
+        }
+    }
+    return Ok(UiFont { font: Box::new(BitmapFont::load_default(core)?), scale: 2 });
+}
+
+
+// the sheet art is drawn 3x larger than its pixels
+const PIXEL_ART_SCALE: i32 = 3;
+
+struct Args {
+    headless: bool,
+    width: i32,
+    height: i32,
+    // stop after this many frames, headless runs have no window to close
+    max_frames: Option<u64>,
+    mob_count: i32,
+    // on by default in debug builds
+    hot_reload: bool,
+    // draw the world at 1/PIXEL_ART_SCALE of the window and upscale it
+    pixel_art: bool,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args {
+        headless: false,
+        width: RendererConfig::DEFAULT_WIDTH,
+        height: RendererConfig::DEFAULT_HEIGHT,
+        max_frames: None, mob_count: 20, hot_reload: cfg!(debug_assertions), pixel_art: false
+    };
+    let mut it = std::env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--headless" => args.headless = true,
+            "--hot-reload" => args.hot_reload = true,
+            "--no-hot-reload" => args.hot_reload = false,
+            "--pixel-art" => args.pixel_art = true,
+            // --size 1600x900
+            "--size" => {
+                let size = it.next().and_then(|s| s.split_once('x').map(|(w, h)| (w.parse().ok(), h.parse().ok())));
+                if let Some((Some(width), Some(height))) = size {
+                    args.width = width;
+                    args.height = height;
+                }
+            },
+            "--frames" => args.max_frames = it.next().and_then(|n| n.parse().ok()),
+            "--mobs" => args.mob_count = it.next().and_then(|n| n.parse().ok()).unwrap_or(args.mob_count),
+            _ => eprintln!("unknown argument: {}", arg),
+        }
+    }
+    return args;
+}
+
+
 fn main() -> Result<(), SDLErrs> {
-    let mut core = Renderer::new("movement test\0")?;
-    let sprite_sheet = core.load_texture("./assets/sprites.png\0")?;
-    let mut fps_ctrl = FpsCapDeltaTime::new(60);
+    let args = parse_args();
+    let backend = if args.headless { RendererBackend::Headless } else { RendererBackend::Window };
+    let mut core = RendererConfig::new("movement test")
+        .size(args.width, args.height)
+        .backend(backend)
+        .resizable(true)
+        .build()?;
+    // cosmetic, the game starts without it
+    if let Some(window) = core.window() {
+        if let Err(err) = window.set_icon("./assets/fish_idle.png") {
+            eprintln!("window icon not set: {:?}", err);
+        }
+    }
+    let (screen_w, screen_h) = core.screen_size();
+    let screen = ScreenSize { width: screen_w, height: screen_h };
+    let mut assets = AssetServer::new();
+    assets.set_hot_reload(args.hot_reload);
+    let sheet_handle = assets.load_sheet(&mut core, "./assets/sprites.png", "./assets/sprites.json")?;
+    let ui_font = load_ui_font(&mut core)?;
+    let fps_ctrl = FpsCapDeltaTime::new(60);
 
     let mut world = World::new();
 
-    const SPAWN_Y: i32 = WINDOW_HEIGHT / 2;
-    const VEL: f32 = 190.0;
-    const SPAWN_X: i32 = WINDOW_WIDHT / 2;
+    let spawn_y = screen.height / 2;
+    let spawn_x = screen.width / 2;
 
-    let pla = world.spawn()
-                   .insert(PlayerComp {})
-                   .insert(SpriteComp { srs: Rect::new(16 * 3, 16 * 3, 16 * 3, 17 * 3), pos: Point2D::new(SPAWN_X, SPAWN_Y) })
-                   .insert(MovementComp { position: Vector2D::new(SPAWN_X as f32, SPAWN_Y as f32) })
-                   .insert(AccelerationComp { acceleration: Vector2D::def() })
-                   .id();
+    world.insert_resource(SpatialHash::new(DIS_RADIUS));
 
-    static MOB_LIST: [(i32, i32, i32, i32); 3] = [
-        (0 * 3, 0 * 3, 16 * 3, 16 * 3),
-        (16 * 3, 0 * 3, 16 * 3, 16 * 3),
-        (0 * 3, 16 * 3, 16 * 3, 17 * 3)];
+    let mut camera = Camera2D::new(screen.width, screen.height);
+    if args.pixel_art {
+        let canvas = RenderCanvas::new(&mut core, screen.width / PIXEL_ART_SCALE, screen.height / PIXEL_ART_SCALE)?;
+        // letterboxing is done by the canvas, in real window pixels
+        core.set_logical_size(0, 0)?;
+        camera = Camera2D::new(canvas.width, canvas.height);
+        // shows the pre-scaled art at its own pixel size, 1x art would keep a zoom of 1
+        camera.zoom = 1.0 / PIXEL_ART_SCALE as f32;
+        world.insert_non_send_resource(canvas);
+    }
+    camera.bounds = Some(game::world_bounds(&screen));
+    camera.look_at(Point2D::new(spawn_x, spawn_y));
+    world.insert_resource(camera);
+    world.insert_resource(screen);
+    world.insert_resource(DrawList::default());
+    world.insert_resource(RenderStats::default());
+    world.insert_resource(ScreenshotRequest::default());
+    world.insert_resource(Score::default());
+    world.insert_resource(DebugOverlay::new(DIS_RADIUS, DIS_RADIUS));
+
+    // sprite sheet frame or the whole texture, scale, max speed
+    static MOB_LIST: [(&str, Option<&str>, i32, f32); 6] = [
+        ("./assets/sprites.png", Some("green_bandit"), 1, 190.0),
+        ("./assets/sprites.png", Some("mummy_bandit"), 1, 150.0),
+        ("./assets/sprites.png", Some("fish_bandit"), 1, 230.0),
+        ("./assets/bandit.png", None, 3, 170.0),
+        ("./assets/gree_bandit.png", None, 3, 210.0),
+        ("./assets/fish_bandit.png", None, 2, 200.0)];
+
+    let mut mob_kinds = Vec::new();
+    for (path, frame_name, scale, speed) in MOB_LIST.iter() {
+        let texture = assets.load_texture(&mut core, path)?;
+        let (w, h) = assets.texture(texture).map_or((0, 0), |t| t.size());
+        let src = match frame_name {
+            Some(name) => assets.sheet(sheet_handle).and_then(|sheet| sheet.frame(name)).ok_or(SDLErrs::LoadSheetMetaErr)?,
+            None => Rect::new(0, 0, w, h),
+        };
+        mob_kinds.push((texture, src, *frame_name, *scale, *speed));
+    }
+
+    let spear_texture = assets.load_texture(&mut core, "./assets/weapon_spear.png")?;
+    let sprite_sheet = assets.sheet(sheet_handle).ok_or(SDLErrs::LoadSheetMetaErr)?;
+    let player_src = sprite_sheet.frame("fish_player").ok_or(SDLErrs::LoadSheetMetaErr)?;
+
+    // without a "player_idle" tag in the sheet the player keeps showing `player_src`
+    let player = world.spawn()
+         .insert(PlayerComp {})
+         .insert(AnimationComp::new(sprite_sheet.meta.clips(), "player_idle"))
+         .insert(SheetSpriteComp { sheet: sheet_handle, frame: "fish_player".to_string() })
+         .insert(SpriteComp::new(sprite_sheet.texture, player_src, Point2D::new(spawn_x, spawn_y)))
+         .insert(MovementComp { position: Vector2D::new(spawn_x as f32, spawn_y as f32), prev_position: Vector2D::new(spawn_x as f32, spawn_y as f32) })
+         .insert(AccelerationComp { acceleration: Vector2D::def(), last_acceleration: Vector2D::def() })
+         .insert(VelocityComp { velocity: Vector2D::def() })
+         .insert(game::PLAYER_SPEED)
+         .insert(ColliderComp { shape: ColliderShape::Circle { radius: 16.0 }, offset: Vector2D::new(8.0 * 3.0, 9.0 * 3.0) })
+         .insert(HealthComp::new(5, 1.0))
+         .id();
+
+    // points right, held 8px from its butt
+    const SPEAR_SCALE: i32 = 2;
+    world.spawn()
+         .insert(SpriteComp {
+             scale: SPEAR_SCALE,
+             pivot: Some(Point2D::new(8 * SPEAR_SCALE, 3 * SPEAR_SCALE)),
+             ..SpriteComp::new(spear_texture, Rect::new(0, 0, 30, 6), Point2D::new(spawn_x, spawn_y))
+         })
+         .insert(WeaponComp { owner: player })
+         .insert(ContactDamageComp { damage: 1, knockback: 250.0 });
 
     let mut rng = rand::thread_rng();
 
 
-    for i in 0..20 {
-        let r = rng.gen_range(0..3);
-        let mob_src = Rect::new(MOB_LIST[r].0, MOB_LIST[r].1, MOB_LIST[r].2, MOB_LIST[r].3);
-        let (rand_x, rand_y) = random_pos_xy(i);
+    for i in 0..args.mob_count {
+        let (mob_texture, mob_src, mob_frame, mob_scale, mob_speed) = mob_kinds[rng.gen_range(0..mob_kinds.len())];
+        let (rand_x, rand_y) = random_pos_xy(i, &screen);
+        let mob_sprite = SpriteComp { scale: mob_scale, ..SpriteComp::new(mob_texture, mob_src, Point2D::new(rand_x, rand_y)) };
 
         let random_number = rng.gen_bool(0.5);
         let (x, y) = if random_number { (-1.0, 1.0) } else { (1.0, -1.0) };
 
-        world.spawn()
-             .insert(SpriteComp { srs: mob_src, pos: Point2D::new(rand_x, rand_y) })
-             .insert(MovementComp { position: Vector2D::new(rand_x as f32, rand_y as f32) })
-             .insert(AccelerationComp { acceleration: Vector2D::def() })
-             .insert(MobComp { rotate_dir: Vector2D::new(x, y) });
+        let mut mob = world.spawn();
+        mob.insert(mob_sprite)
+           .insert(MovementComp { position: Vector2D::new(rand_x as f32, rand_y as f32), prev_position: Vector2D::new(rand_x as f32, rand_y as f32) })
+           .insert(AccelerationComp { acceleration: Vector2D::def(), last_acceleration: Vector2D::def() })
+           .insert(VelocityComp { velocity: Vector2D::def() })
+           .insert(SpeedComp { max_speed: mob_speed, thrust: mob_speed * 8.0, friction: 4.0 })
+           .insert(ColliderComp { shape: ColliderShape::Circle { radius: 14.0 }, offset: Vector2D::new((mob_src.width() * mob_scale) as f32 / 2.0, (mob_src.height() * mob_scale) as f32 / 2.0) })
+           .insert(HealthComp::new(3, 0.2))
+           .insert(ContactDamageComp { damage: 1, knockback: 400.0 })
+           .insert(MobComp { rotate_dir: Vector2D::new(x, y) });
+        if let Some(frame) = mob_frame {
+            mob.insert(SheetSpriteComp { sheet: sheet_handle, frame: frame.to_string() });
+        }
     }
 
-    let mut render_all = world.query::<&SpriteComp>();
-    let mut pla_acceleration = world.query::<(&mut AccelerationComp, With<PlayerComp>)>();
+    world.insert_resource(events::get_keyboard_state());
+    world.insert_resource(fps_ctrl);
+    world.insert_resource(LastSDLErr(None));
+    world.insert_resource(AppExit(false));
+    world.insert_resource(Events::<ContactEvent>::default());
+    world.insert_resource(Events::<DamageEvent>::default());
+    world.insert_resource(Events::<DeathEvent>::default());
+    world.insert_non_send_resource(core);
+    world.insert_non_send_resource(assets);
+    world.insert_non_send_resource(ui_font);
+
+    let result = run(&mut world, &args);
+
+    // textures have to be destroyed before the renderer that created them
+    world.non_send_resource_mut::<AssetServer>().clear();
+    world.remove_non_send_resource::<UiFont>();
+    world.remove_non_send_resource::<RenderCanvas>();
+    world.remove_non_send_resource::<Renderer>();
+    return result;
+}
+
 
-    let mut update_mob = world.query::<(&mut AccelerationComp, &MobComp, &MovementComp)>();
-    let mut update_mob_self_collition1 = world.query::<(&mut AccelerationComp, &MobComp, Entity, &MovementComp)>();
-    let mut update_mob_self_collition2 = world.query::<(&AccelerationComp, &MobComp, Entity, &MovementComp)>();
-    let mut update_all = world.query::<(&mut AccelerationComp, &mut SpriteComp, &mut MovementComp)>();
+// the pixel-art canvas is refitted every frame, a configured logical size is scaled to the
+// window by SDL, otherwise the view grows with the window
+fn handle_resize(world: &mut World, width: i32, height: i32) -> Result<(), SDLErrs> {
+    if width <= 0 || height <= 0 {
+        return Ok(());
+    }
+    // the canvas turns the logical size off and lays the UI out in window pixels
+    let has_canvas = world.get_non_send_resource::<RenderCanvas>().is_some();
+    if !has_canvas && world.non_send_resource::<Renderer>().configured_logical_size().is_some() {
+        return Ok(());
+    }
+    *world.resource_mut::<ScreenSize>() = ScreenSize { width, height };
+    if has_canvas {
+        return Ok(());
+    }
+    world.non_send_resource_mut::<Renderer>().set_logical_size(width, height)?;
+
+    let mut camera = world.resource_mut::<Camera2D>();
+    camera.viewport_w = width;
+    camera.viewport_h = height;
+    camera.clamp_to_bounds();
+    return Ok(());
+}
 
 
-    let keys = events::get_keyboard_state();
+fn run(world: &mut World, args: &Args) -> Result<(), SDLErrs> {
+    let mut fixed_update = systems::fixed_schedule();
+    game::add_game_systems(&mut fixed_update);
+    let mut frame_update = systems::frame_schedule();
+    game::add_frame_systems(&mut frame_update);
 
 
     let mut is_running = true;
+    let mut frame_count: u64 = 0;
 //--------- LOOP
     while is_running {
-        fps_ctrl.start();
+        world.resource_mut::<FpsCapDeltaTime>().start();
 
 //--------- EVENT
         {
-            for (mut dir, _pla) in pla_acceleration.iter_mut(&mut world) {
-                if keys.is_scancode_pressed(events::ScanCode::A) {
-                    dir.acceleration.x = -1.0;
-                } else if keys.is_scancode_pressed(events::ScanCode::D) {
-                    dir.acceleration.x = 1.0;
-                }
-                if keys.is_scancode_pressed(events::ScanCode::W) {
-                    dir.acceleration.y = -1.0;
-                } else if keys.is_scancode_pressed(events::ScanCode::S) {
-                    dir.acceleration.y = 1.0;
-                }
-
-                if dir.acceleration.x != 0.0 && dir.acceleration.y != 0.0 {
-                    dir.acceleration.x *= std::f32::consts::FRAC_1_SQRT_2;
-                    dir.acceleration.y *= std::f32::consts::FRAC_1_SQRT_2;
-                }
-            }
-
             while let Some(w_event) = events::poll_iter() {
                 match w_event.type_() {
                     events::QUIT => {
                         is_running = false;
                     },
+                    events::KEY_DOWN if w_event.scancode() == events::ScanCode::F3 && !w_event.is_repeat() => {
+                        world.resource_mut::<DebugOverlay>().toggle();
+                    },
+                    events::KEY_DOWN if w_event.scancode() == events::ScanCode::F12 && !w_event.is_repeat() => {
+                        world.resource_mut::<ScreenshotRequest>().0 = true;
+                    },
+                    events::KEY_DOWN if w_event.scancode() == events::ScanCode::F11 && !w_event.is_repeat() => {
+                        if let Some(window) = world.non_send_resource::<Renderer>().window() {
+                            // stays in the current mode
+                            if let Err(err) = window.toggle_fullscreen() {
+                                eprintln!("fullscreen toggle failed: {:?}", err);
+                            }
+                        }
+                    },
+                    events::WINDOW_EVENT => {
+                        if let Some((width, height)) = w_event.resized_to() {
+                            handle_resize(world, width, height)?;
+                        }
+                    },
                     _ => {},
                 }
             }
         }
 //--------- UPDATE
-        {
-            const DIS: f32 = 40.0 * 40.0;
-            const DIS2: f32 = 20.0 * 20.0;
-            // mob direction of movement and/or rotaion around player
-            let pla_position = (*world.get::<MovementComp>(pla).unwrap()).position.clone();
-            for (mut dir1, mob1, movement1) in update_mob.iter_mut(&mut world) {
-                let mut dir_x = pla_position.x - movement1.position.x;
-                let mut dir_y = pla_position.y - movement1.position.y;
-                let distance_squared = dir_x * dir_x + dir_y * dir_y;
-
-                let hyp = (dir_x * dir_x + dir_y * dir_y).sqrt();
-                // normalized
-                dir_x /= hyp;
-                dir_y /= hyp;
-
-                if DIS < distance_squared {
-                    dir1.acceleration.x = dir_x ;
-                    dir1.acceleration.y = dir_y;
-                } else {
-                    dir1.acceleration.x = (dir_y) * mob1.rotate_dir.x;
-                    dir1.acceleration.y = (dir_x) * mob1.rotate_dir.y;
-                }
-            }
-
-            // mob other mob collision
-            for (mut dir1, _mob1, entt1, movement1) in unsafe { update_mob_self_collition1.iter_unchecked(&world) } {
-                // collition with other mob
-                'inner: for (dir2, _mob2, entt2,  movement2) in  update_mob_self_collition2.iter(&world) {
-                    if entt1 == entt2 {
-                        continue 'inner;
-                    }
-
-                    let mut x = movement1.position.x - movement2.position.x;
-                    let mut y = movement1.position.y - movement2.position.y;
-                    let dist_squered = x * x + y * y;
-                    if dist_squered > DIS || dist_squered < 0.0  {
-                        continue 'inner;
-                    }
-
-                    let hyp = dist_squered.sqrt();
-                    x /= hyp;
-                    y /= hyp;
-
-
-                    dir1.acceleration.x += x;
-                    dir1.acceleration.y += y;
-                }
-            }
-
-            // update all movement
-            for (mut dir, mut sprite, mut movement) in update_all.iter_mut(&mut world) {
-                movement.position.x += dir.acceleration.x * VEL * fps_ctrl.dt;
-                movement.position.y += dir.acceleration.y * VEL * fps_ctrl.dt;
-
-                dir.acceleration.x = 0.0;
-                dir.acceleration.y = 0.0;
-
-                sprite.pos.set_x(movement.position.x as i32);
-                sprite.pos.set_y(movement.position.y as i32);
-            }
+        while world.resource_mut::<FpsCapDeltaTime>().step() {
+            fixed_update.run(world);
         }
 //--------- RENDER
-        {
-            // core.set_draw_color((10, 10, 30));
-            core.clear();
-
-            // render all sprites
-            // TODO: make id to NOT sort every frame
-            let mut rendr = render_all.iter(&world).collect::<Vec<_>>();
-            rendr.sort_unstable_by_key(|a| a.pos.y() + a.srs.width() / 2);
-            for sprite in rendr {
-                core.renderer_copy(&sprite_sheet, sprite.srs, Rect::new(sprite.pos.x(), sprite.pos.y(), sprite.srs.width(), sprite.srs.height()))?;
-            }
+        frame_update.run(world);
+        if let Some(err) = world.resource_mut::<LastSDLErr>().0.take() {
+            return Err(err);
         }
 
-        core.present();
-        fps_ctrl.end();
+        // change detection and removed components are tracked per frame
+        world.clear_trackers();
+        world.resource_mut::<FpsCapDeltaTime>().end();
+
+        frame_count += 1;
+        if args.max_frames.is_some_and(|max| frame_count >= max) {
+            is_running = false;
+        }
+        if world.resource::<AppExit>().0 {
+            is_running = false;
+        }
     }
     return Ok(());
 }